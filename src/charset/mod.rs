@@ -0,0 +1,385 @@
+use url::{ Url };
+use percent_encoding::{ percent_decode_str };
+
+use crate::config::{ CharsetPolicy, CharsetPolicyAction, CharsetList };
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum Script {
+  Common,
+  Latin,
+  Greek,
+  Cyrillic,
+  Armenian,
+  Hebrew,
+  Arabic,
+  Devanagari,
+  Thai,
+  Georgian,
+  Hangul,
+  Hiragana,
+  Katakana,
+  Bopomofo,
+  Han,
+  Unknown
+}
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum CharacterKind {
+  Control,
+  Bidi,
+  ZeroWidth,
+  NonBmp,
+  Other
+}
+
+pub struct CharacterIssue {
+  pub character: char,
+  pub position: usize,
+  pub charset: CharsetList,
+  pub kind: CharacterKind,
+  pub action: CharsetPolicyAction
+}
+
+pub struct LabelIssue {
+  pub label: String,
+  pub scripts: Vec<Script>,
+  pub lookalike: Option<String>
+}
+
+pub struct HostAnalysis {
+  pub host_unicode: String,
+  pub host_ascii: String,
+  pub issues: Vec<LabelIssue>
+}
+
+pub struct UrlAnalysis {
+  pub action: CharsetPolicyAction,
+  pub characters: Vec<CharacterIssue>,
+  pub host: Option<HostAnalysis>
+}
+
+impl HostAnalysis {
+  pub fn is_suspicious(&self) -> bool {
+    return self.issues.len() > 0;
+  }
+}
+
+impl CharacterIssue {
+  pub fn get_description(&self) -> String {
+    let kind:&str = match self.kind {
+      CharacterKind::Control => "control character",
+      CharacterKind::Bidi => "bidirectional text control",
+      CharacterKind::ZeroWidth => "invisible character",
+      CharacterKind::NonBmp => "character outside the Basic Multilingual Plane",
+      CharacterKind::Other => "non ASCII character"
+    };
+
+    // Don't display characters that change how the text around them is rendered
+    match self.kind {
+      CharacterKind::NonBmp | CharacterKind::Other => {
+        return format!("U+{:04X} '{}' at position {}: {} ({:?})", self.character as u32, self.character, self.position, kind, self.charset);
+      },
+      _ => {
+        return format!("U+{:04X} at position {}: {} ({:?})", self.character as u32, self.position, kind, self.charset);
+      }
+    }
+  }
+}
+
+impl UrlAnalysis {
+  // Text displayed in the warning and error dialogs
+  pub fn get_report(&self) -> String {
+    let max_characters:usize = 20;
+    let mut report:Vec<String> = vec![];
+
+    for issue in self.characters.iter().take(max_characters) {
+      report.push(issue.get_description());
+    }
+    if self.characters.len() > max_characters {
+      report.push(format!("... and {} more characters", self.characters.len() - max_characters));
+    }
+
+    match &self.host {
+      Some(host) if host.host_unicode != host.host_ascii || host.is_suspicious() => {
+        report.push(format!("Host: {}\nPunycode: {}", host.host_unicode, host.host_ascii));
+        for issue in &host.issues {
+          if issue.scripts.len() > 1 {
+            report.push(format!("Label '{}' mixes scripts: {}", issue.label, format_scripts(&issue.scripts)));
+          } else {
+            report.push(format!("Label '{}' only uses {} characters", issue.label, format_scripts(&issue.scripts)));
+          }
+          match &issue.lookalike {
+            Some(lookalike) => { report.push(format!("Label '{}' looks like '{}'", issue.label, lookalike)); },
+            None => { }
+          }
+        }
+      },
+      _ => { }
+    }
+
+    return report.join("\n");
+  }
+}
+
+pub fn analyze_url(url:&str, charset_policy:&CharsetPolicy) -> UrlAnalysis {
+  let mut action:CharsetPolicyAction = CharsetPolicyAction::Allow;
+  let characters:Vec<CharacterIssue>;
+  let host:Option<HostAnalysis>;
+
+  // Removing tracking parameters, expanding short links and unwrapping redirectors re-serialize
+  // the URL, which percent-encodes the characters, so they are decoded before checking
+  characters = analyze_characters(&percent_decode_str(url).decode_utf8_lossy(), charset_policy);
+  for issue in &characters {
+    action = action.max(issue.action);
+  }
+
+  host = match Url::parse(url) {
+    Ok(parsed_url) => {
+      match parsed_url.host_str() {
+        Some(host_str) => Some(analyze_host(host_str)),
+        None => None
+      }
+    },
+    Err(..) => None
+  };
+
+  match &host {
+    Some(host_analysis) if host_analysis.is_suspicious() => {
+      action = action.max(charset_policy.idn.unwrap_or(CharsetPolicyAction::Warn));
+    },
+    _ => { }
+  }
+
+  return UrlAnalysis { action: action, characters: characters, host: host };
+}
+
+// Check each label of the host for mixed scripts and characters that look like Latin letters.
+// 'Url::parse' already converted the host to punycode, so decode it before checking.
+pub fn analyze_host(host:&str) -> HostAnalysis {
+  let host_ascii:String = host.to_lowercase();
+  let host_unicode:String;
+  let mut issues:Vec<LabelIssue> = vec![];
+
+  (host_unicode, _) = idna::domain_to_unicode(&host_ascii);
+
+  for label in host_unicode.split(".") {
+    let scripts:Vec<Script> = get_scripts(label);
+    let lookalike:Option<String> = get_lookalike(label);
+
+    if !is_allowed_script_mix(&scripts) || lookalike.is_some() {
+      issues.push(LabelIssue { label: label.to_string(), scripts: scripts, lookalike: lookalike });
+    }
+  }
+
+  return HostAnalysis { host_unicode: host_unicode, host_ascii: host_ascii, issues: issues };
+}
+
+// Check every character of the URL against the charset policy. Characters are grouped by
+// the number of bytes required to encode them: ASCII (UTF8), the Basic Multilingual Plane (UTF16)
+// and the supplementary planes (UTF32). Control, bidirectional and invisible characters are
+// always reported with at least a warning, as they can hide the real URL.
+pub fn analyze_characters(url:&str, charset_policy:&CharsetPolicy) -> Vec<CharacterIssue> {
+  let mut issues:Vec<CharacterIssue> = vec![];
+
+  for (index, c) in url.chars().enumerate() {
+    let charset:CharsetList;
+    let kind:CharacterKind;
+    let mut action:CharsetPolicyAction;
+
+    if c.is_ascii() && !c.is_ascii_control() {
+      continue;
+    }
+
+    charset = get_charset(c);
+    kind = get_character_kind(c);
+    action = match charset {
+      CharsetList::Utf8 => charset_policy.utf8,
+      CharsetList::Utf16 => charset_policy.utf16,
+      CharsetList::Utf32 => charset_policy.utf32
+    };
+    if kind == CharacterKind::Control || kind == CharacterKind::Bidi || kind == CharacterKind::ZeroWidth {
+      action = action.max(CharsetPolicyAction::Warn);
+    }
+
+    if action != CharsetPolicyAction::Allow {
+      issues.push(CharacterIssue { character: c, position: index + 1, charset: charset, kind: kind, action: action });
+    }
+  }
+
+  return issues;
+}
+
+fn get_charset(c:char) -> CharsetList {
+  return match c as u32 {
+    0x00..=0x7F => CharsetList::Utf8,
+    0x80..=0xFFFF => CharsetList::Utf16,
+    _ => CharsetList::Utf32
+  };
+}
+
+fn get_character_kind(c:char) -> CharacterKind {
+  return match c as u32 {
+    0x061C | 0x200E | 0x200F | 0x202A..=0x202E | 0x2066..=0x2069 => CharacterKind::Bidi,
+    0x00AD | 0x180E | 0x200B..=0x200D | 0x2060..=0x2064 | 0xFEFF => CharacterKind::ZeroWidth,
+    _ if c.is_control() => CharacterKind::Control,
+    0x10000..=0x10FFFF => CharacterKind::NonBmp,
+    _ => CharacterKind::Other
+  };
+}
+
+fn get_scripts(label:&str) -> Vec<Script> {
+  let mut scripts:Vec<Script> = vec![];
+
+  for c in label.chars() {
+    let script:Script = get_script(c);
+    if script != Script::Common && !scripts.contains(&script) {
+      scripts.push(script);
+    }
+  }
+
+  return scripts;
+}
+
+// Based on the 'Highly Restrictive' level of Unicode Technical Standard #39, a label can
+// use a single script, or Latin combined with the scripts used to write CJK languages.
+fn is_allowed_script_mix(scripts:&Vec<Script>) -> bool {
+  let allowed_combinations:Vec<Vec<Script>> = vec![
+    vec![Script::Latin, Script::Han, Script::Hiragana, Script::Katakana],
+    vec![Script::Latin, Script::Han, Script::Bopomofo],
+    vec![Script::Latin, Script::Han, Script::Hangul]
+  ];
+
+  if scripts.len() <= 1 {
+    return true;
+  }
+
+  return allowed_combinations.iter().any( |allowed| scripts.iter().all( |s| allowed.contains(s) ) );
+}
+
+// Returns the label spelled with Latin letters if it has non Latin characters that look
+// like Latin letters and the remaining characters are Latin or common characters.
+fn get_lookalike(label:&str) -> Option<String> {
+  let mut lookalike:String = String::new();
+  let mut has_confusable:bool = false;
+
+  for c in label.chars() {
+    match get_confusable(c) {
+      Some(latin) => {
+        lookalike.push(latin);
+        has_confusable = true;
+      },
+      None => {
+        let script:Script = get_script(c);
+        if script != Script::Latin && script != Script::Common {
+          return None;
+        }
+        lookalike.push(c);
+      }
+    }
+  }
+
+  if has_confusable {
+    return Some(lookalike);
+  }
+  return None;
+}
+
+fn get_confusable(c:char) -> Option<char> {
+  let latin:char = match c {
+    // Cyrillic
+    '\u{0430}' => 'a', '\u{0432}' => 'b', '\u{0435}' => 'e', '\u{043A}' => 'k', '\u{043C}' => 'm',
+    '\u{043D}' => 'h', '\u{043E}' => 'o', '\u{0440}' => 'p', '\u{0441}' => 'c', '\u{0442}' => 't',
+    '\u{0443}' => 'y', '\u{0445}' => 'x', '\u{0455}' => 's', '\u{0456}' => 'i', '\u{0458}' => 'j',
+    '\u{04BB}' => 'h', '\u{04CF}' => 'l', '\u{0501}' => 'd', '\u{051B}' => 'q', '\u{051D}' => 'w',
+    // Greek
+    '\u{03B1}' => 'a', '\u{03B9}' => 'i', '\u{03BA}' => 'k', '\u{03BD}' => 'v', '\u{03BF}' => 'o',
+    '\u{03C1}' => 'p', '\u{03C4}' => 't', '\u{03C5}' => 'u', '\u{03C7}' => 'x', '\u{03B5}' => 'e',
+    // Armenian
+    '\u{0561}' => 'w', '\u{0563}' => 'q', '\u{0566}' => 'q', '\u{0570}' => 'h', '\u{0578}' => 'n',
+    '\u{057D}' => 'u', '\u{0585}' => 'o',
+    _ => { return None; }
+  };
+  return Some(latin);
+}
+
+fn get_script(c:char) -> Script {
+  let code_point:u32 = c as u32;
+
+  return match code_point {
+    0x30..=0x39 | 0x2D | 0x5F => Script::Common,
+    0x41..=0x5A | 0x61..=0x7A => Script::Latin,
+    0x00..=0x7F => Script::Common,
+    0xC0..=0xD6 | 0xD8..=0xF6 | 0xF8..=0x24F => Script::Latin,
+    0x300..=0x36F => Script::Common,
+    0x370..=0x3FF | 0x1F00..=0x1FFF => Script::Greek,
+    0x400..=0x52F | 0x1C80..=0x1C8F | 0x2DE0..=0x2DFF | 0xA640..=0xA69F => Script::Cyrillic,
+    0x530..=0x58F => Script::Armenian,
+    0x590..=0x5FF => Script::Hebrew,
+    0x600..=0x6FF | 0x750..=0x77F | 0x8A0..=0x8FF | 0xFB50..=0xFDFF | 0xFE70..=0xFEFF => Script::Arabic,
+    0x900..=0x97F => Script::Devanagari,
+    0xE00..=0xE7F => Script::Thai,
+    0x10A0..=0x10FF => Script::Georgian,
+    0x1100..=0x11FF | 0x3130..=0x318F | 0xAC00..=0xD7AF => Script::Hangul,
+    0x1E00..=0x1EFF | 0x2C60..=0x2C7F | 0xA720..=0xA7FF | 0xAB30..=0xAB6F => Script::Latin,
+    0x3040..=0x309F => Script::Hiragana,
+    0x30A0..=0x30FF | 0x31F0..=0x31FF => Script::Katakana,
+    0x3100..=0x312F => Script::Bopomofo,
+    0x3400..=0x4DBF | 0x4E00..=0x9FFF | 0xF900..=0xFAFF | 0x20000..=0x2FA1F => Script::Han,
+    _ => Script::Unknown
+  };
+}
+
+fn format_scripts(scripts:&Vec<Script>) -> String {
+  return scripts.iter().map( |s| format!("{:?}", s) ).collect::<Vec<String>>().join(", ");
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::config::{ TrackingPolicy };
+  use crate::webclient;
+
+  fn get_policy() -> CharsetPolicy {
+    return CharsetPolicy {
+      utf8: CharsetPolicyAction::Allow,
+      utf16: CharsetPolicyAction::Allow,
+      utf32: CharsetPolicyAction::Allow,
+      idn: Some(CharsetPolicyAction::Warn)
+    };
+  }
+
+  #[test]
+  fn detects_characters_after_removing_tracking_parameters() {
+    let tracking_policy:Option<TrackingPolicy> = Some(TrackingPolicy {
+      strip: true,
+      parameters: vec![String::from("utm_*")],
+      exceptions: vec![]
+    });
+    let stripped_url:webclient::StrippedUrl;
+    let analysis:UrlAnalysis;
+    let kinds:Vec<CharacterKind>;
+
+    stripped_url = webclient::strip_tracking_parameters("https://example.com/pay\u{202E}gpj.exe?id=1\u{200B}&utm_source=x", &tracking_policy).unwrap();
+    assert!(!stripped_url.url.contains("\u{202E}"));
+
+    analysis = analyze_url(&stripped_url.url, &get_policy());
+    kinds = analysis.characters.iter().map( |c| c.kind ).collect();
+    assert_eq!(analysis.action, CharsetPolicyAction::Warn);
+    assert!(kinds.contains(&CharacterKind::Bidi));
+    assert!(kinds.contains(&CharacterKind::ZeroWidth));
+  }
+
+  #[test]
+  fn detects_encoded_characters() {
+    let analysis:UrlAnalysis = analyze_url("https://example.com/pay%E2%80%AEgpj.exe", &get_policy());
+    assert_eq!(analysis.action, CharsetPolicyAction::Warn);
+    assert_eq!(analysis.characters[0].character, '\u{202E}');
+  }
+
+  #[test]
+  fn allows_ascii_url() {
+    let analysis:UrlAnalysis = analyze_url("https://example.com/search?q=a%20b&id=1", &get_policy());
+    assert_eq!(analysis.action, CharsetPolicyAction::Allow);
+    assert_eq!(analysis.characters.len(), 0);
+  }
+}
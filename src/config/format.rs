@@ -0,0 +1,104 @@
+use serde_json::{ Value };
+use std::path::{ PathBuf };
+
+use crate::config::{ BW_CONFIG, BW_CONFIG_TOML, BW_CONFIG_YAML };
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ConfigFormat {
+  Json,
+  Toml,
+  Yaml
+}
+
+impl ConfigFormat {
+  pub fn from_name(name:&str) -> Option<ConfigFormat> {
+    match name.to_lowercase().as_str() {
+      "json" => { return Some(ConfigFormat::Json); },
+      "toml" => { return Some(ConfigFormat::Toml); },
+      "yaml" | "yml" => { return Some(ConfigFormat::Yaml); },
+      _ => { return None; }
+    }
+  }
+
+  // Files without a known extension are JSON, as 'config.json' was the only format
+  pub fn from_path(path:&PathBuf) -> ConfigFormat {
+    match path.extension() {
+      Some(extension) => { return ConfigFormat::from_name(&extension.to_string_lossy()).unwrap_or(ConfigFormat::Json); },
+      None => { return ConfigFormat::Json; }
+    }
+  }
+
+  pub fn get_file_name(&self) -> &'static str {
+    match self {
+      ConfigFormat::Json => { return BW_CONFIG; },
+      ConfigFormat::Toml => { return BW_CONFIG_TOML; },
+      ConfigFormat::Yaml => { return BW_CONFIG_YAML; }
+    }
+  }
+
+  pub fn parse(&self, contents:&str) -> Result<Value, String> {
+    match self {
+      ConfigFormat::Json => { return serde_json::from_str(contents).map_err( |e| e.to_string() ); },
+      ConfigFormat::Toml => { return toml::from_str(contents).map_err( |e| e.to_string() ); },
+      ConfigFormat::Yaml => { return serde_norway::from_str(contents).map_err( |e| e.to_string() ); }
+    }
+  }
+
+  pub fn serialize(&self, document:&Value) -> Result<String, String> {
+    match self {
+      ConfigFormat::Json => { return serde_json::to_string_pretty(document).map_err( |e| e.to_string() ); },
+      ConfigFormat::Toml => { return toml::to_string_pretty(&remove_null(document)).map_err( |e| e.to_string() ); },
+      ConfigFormat::Yaml => { return serde_norway::to_string(document).map_err( |e| e.to_string() ); }
+    }
+  }
+}
+
+// Settings set to null are the same as settings that aren't in the file
+pub fn is_same_document(document:&Value, other:&Value) -> bool {
+  return remove_null(document) == remove_null(other);
+}
+
+// TOML doesn't have null, the settings that aren't set are left out instead,
+// which is read back the same way
+fn remove_null(value:&Value) -> Value {
+  match value {
+    Value::Object(map) => {
+      return Value::Object(map.iter().filter( |(_, v)| !v.is_null() ).map( |(k, v)| (k.clone(), remove_null(v)) ).collect());
+    },
+    Value::Array(values) => { return Value::Array(values.iter().map( |v| remove_null(v) ).collect()); },
+    _ => { return value.clone(); }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::config::{ load_test_fixture };
+
+  #[test]
+  fn converts_without_changes() {
+    let documents:Vec<Value> = vec![
+      serde_json::from_str(include_str!("../../resources/config.json")).unwrap(),
+      load_test_fixture("1.0.0.json"),
+      load_test_fixture("1.0.1.json"),
+      load_test_fixture("unversioned.json"),
+      load_test_fixture("2.json")
+    ];
+
+    for document in documents {
+      for format in [ConfigFormat::Json, ConfigFormat::Toml, ConfigFormat::Yaml] {
+        let contents:String = format.serialize(&document).unwrap();
+        assert!(is_same_document(&format.parse(&contents).unwrap(), &document), "{:?}\n{}", format, contents);
+      }
+    }
+  }
+
+  #[test]
+  fn reads_format_from_file_name() {
+    assert_eq!(ConfigFormat::from_path(&PathBuf::from("/tmp/config.toml")), ConfigFormat::Toml);
+    assert_eq!(ConfigFormat::from_path(&PathBuf::from("/tmp/config.yml")), ConfigFormat::Yaml);
+    assert_eq!(ConfigFormat::from_path(&PathBuf::from("/tmp/config")), ConfigFormat::Json);
+    assert_eq!(ConfigFormat::from_name("YAML"), Some(ConfigFormat::Yaml));
+    assert_eq!(ConfigFormat::from_name("xml"), None);
+  }
+}
@@ -0,0 +1,214 @@
+use serde_json::{ Value };
+use std::path::{ PathBuf };
+use std::time::{ SystemTime, UNIX_EPOCH };
+
+// Version of the configuration files written by this release, increase it when adding a migration
+pub static CURRENT_VERSION:u64 = 2;
+
+// Each migration upgrades the configuration from the previous version. Sections added by a
+// version are copied from the default configuration, values set by the user are never replaced.
+fn get_migrations() -> Vec<(u64, fn(&mut Value, &Value))> {
+  return vec![
+    (1, add_charset_policy),
+    (2, add_url_policies)
+  ];
+}
+
+// Files written before the 'version' field was added are version 1 if they have
+// the charset policy, added in 1.0.1, or version 0 otherwise
+pub fn get_version(document:&Value) -> u64 {
+  match document.get("version").and_then( |v| v.as_u64() ) {
+    Some(version) => { return version; },
+    None => {
+      if !document["settings"]["charset_policy"].is_null() {
+        return 1;
+      }
+      return 0;
+    }
+  }
+}
+
+// Upgrade the configuration to the current version, returns false if it was already up to date
+pub fn migrate(document:&mut Value, defaults:&Value) -> Result<bool, String> {
+  let version:u64 = get_version(document);
+
+  if !document["settings"].is_object() || !document["browsers_list"].is_array() {
+    return Err(String::from("'settings' or 'browsers_list' is missing"));
+  }
+  if version > CURRENT_VERSION {
+    return Err(format!("version {} is newer than the supported version {}", version, CURRENT_VERSION));
+  }
+  if version == CURRENT_VERSION && document.get("version").is_some() {
+    return Ok(false);
+  }
+
+  for (migration_version, migration) in get_migrations() {
+    if version < migration_version {
+      migration(document, defaults);
+    }
+  }
+  document["version"] = Value::from(CURRENT_VERSION);
+
+  return Ok(true);
+}
+
+// Backup file for the configuration before it is migrated, e.g. 'config.json.20240131-093000.bak',
+// a number is added if there is already a backup from the same second
+pub fn get_backup_file(file_path:&PathBuf) -> PathBuf {
+  let seconds:u64 = match SystemTime::now().duration_since(UNIX_EPOCH) {
+    Ok(duration) => duration.as_secs(),
+    Err(..) => 0
+  };
+  let file_name:String = format!("{}.{}", file_path.file_name().unwrap().to_string_lossy(), get_timestamp(seconds));
+  let mut backup_file:PathBuf = file_path.with_file_name(format!("{}.bak", file_name));
+  let mut count:u32 = 1;
+
+  while backup_file.exists() {
+    backup_file = file_path.with_file_name(format!("{}-{}.bak", file_name, count));
+    count = count + 1;
+  }
+  return backup_file;
+}
+
+// UTC date and time, without adding a dependency just for the backup file name
+fn get_timestamp(seconds:u64) -> String {
+  let days:i64 = (seconds / 86400) as i64;
+  let time:u64 = seconds % 86400;
+
+  // Convert the days since 1970-01-01 to a date
+  let z:i64 = days + 719468;
+  let era:i64 = z.div_euclid(146097);
+  let day_of_era:i64 = z - era * 146097;
+  let year_of_era:i64 = (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146096) / 365;
+  let day_of_year:i64 = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+  let mp:i64 = (5 * day_of_year + 2) / 153;
+  let day:i64 = day_of_year - (153 * mp + 2) / 5 + 1;
+  let month:i64 = if mp < 10 { mp + 3 } else { mp - 9 };
+  let year:i64 = year_of_era + era * 400 + if month <= 2 { 1 } else { 0 };
+
+  return format!("{:04}{:02}{:02}-{:02}{:02}{:02}", year, month, day, time / 3600, time % 3600 / 60, time % 60);
+}
+
+// Sections set to null are also missing
+fn copy_missing(target:&mut Value, defaults:&Value, key:&str) {
+  if target[key].is_null() && !defaults[key].is_null() {
+    target[key] = defaults[key].clone();
+  }
+}
+
+// 1.0.0 configurations, before the charset policy
+fn add_charset_policy(document:&mut Value, defaults:&Value) {
+  copy_missing(&mut document["settings"], &defaults["settings"], "charset_policy");
+}
+
+// URL handling sections: redirectors, tracking parameters, schemes, short links, local files,
+// denylist, threat list, intranet, rewrite rules and routing rules
+fn add_url_policies(document:&mut Value, defaults:&Value) {
+  let settings:[&str; 8] = [
+    "redirect_policy", "tracking_policy", "schemes", "shortlink_policy",
+    "file_handlers", "denylist", "threat_policy", "intranet"
+  ];
+
+  copy_missing(&mut document["settings"]["charset_policy"], &defaults["settings"]["charset_policy"], "idn");
+  for key in settings {
+    copy_missing(&mut document["settings"], &defaults["settings"], key);
+  }
+  copy_missing(document, defaults, "rewrite_rules");
+  copy_missing(document, defaults, "rules");
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::config::{ Configuration, load_test_fixture };
+
+  fn get_defaults() -> Value {
+    return serde_json::from_str(include_str!("../../resources/config.json")).unwrap();
+  }
+
+  fn assert_current(document:&Value) -> Configuration {
+    assert_eq!(get_version(document), CURRENT_VERSION);
+    return serde_json::from_value(document.clone()).unwrap();
+  }
+
+  #[test]
+  fn default_configuration_is_current() {
+    let mut document:Value = get_defaults();
+    assert_eq!(migrate(&mut document, &get_defaults()), Ok(false));
+    assert_current(&document);
+  }
+
+  #[test]
+  fn migrates_1_0_0() {
+    let mut document:Value = load_test_fixture("1.0.0.json");
+    assert_eq!(get_version(&document), 0);
+    assert_eq!(migrate(&mut document, &get_defaults()), Ok(true));
+
+    let configuration:Configuration = assert_current(&document);
+    assert_eq!(document["settings"]["charset_policy"], get_defaults()["settings"]["charset_policy"]);
+    assert!(configuration.settings.tracking_policy.is_some());
+    assert_eq!(configuration.settings.homepage, "https://duckduckgo.com/");
+    assert_eq!(configuration.browsers_list.len(), 2);
+  }
+
+  #[test]
+  fn migrates_1_0_1() {
+    let mut document:Value = load_test_fixture("1.0.1.json");
+    assert_eq!(get_version(&document), 1);
+    assert_eq!(migrate(&mut document, &get_defaults()), Ok(true));
+
+    let configuration:Configuration = assert_current(&document);
+    // The user charset policy is kept, only the new 'idn' setting is added
+    assert_eq!(document["settings"]["charset_policy"]["utf16"], "Block");
+    assert_eq!(document["settings"]["charset_policy"]["idn"], "Warn");
+    assert_eq!(configuration.rules.unwrap().len(), 0);
+    assert!(configuration.browsers_list[0].auto_launch.is_some());
+  }
+
+  #[test]
+  fn migrates_unversioned_with_url_policies() {
+    let mut document:Value = load_test_fixture("unversioned.json");
+    assert_eq!(get_version(&document), 1);
+    assert_eq!(migrate(&mut document, &get_defaults()), Ok(true));
+
+    let configuration:Configuration = assert_current(&document);
+    // Sections already in the file are kept as they are
+    assert_eq!(document["settings"]["tracking_policy"]["strip"], false);
+    assert_eq!(document["settings"]["schemes"], serde_json::json!(["http", "https"]));
+    assert_eq!(document["settings"]["charset_policy"]["idn"], "Block");
+    assert_eq!(configuration.rewrite_rules.unwrap().len(), 1);
+    assert!(configuration.settings.denylist.is_some());
+    assert!(configuration.settings.intranet.is_some());
+  }
+
+  #[test]
+  fn keeps_current_version() {
+    let mut document:Value = load_test_fixture("2.json");
+    let original:Value = document.clone();
+    assert_eq!(migrate(&mut document, &get_defaults()), Ok(false));
+    assert_eq!(document, original);
+
+    let configuration:Configuration = assert_current(&document);
+    assert_eq!(configuration.rules.unwrap()[0].name, "Work");
+  }
+
+  #[test]
+  fn rejects_newer_version() {
+    let mut document:Value = load_test_fixture("2.json");
+    document["version"] = Value::from(CURRENT_VERSION + 1);
+    assert!(migrate(&mut document, &get_defaults()).is_err());
+  }
+
+  #[test]
+  fn rejects_invalid_document() {
+    let mut document:Value = serde_json::json!({ "browsers_list": [] });
+    assert!(migrate(&mut document, &get_defaults()).is_err());
+  }
+
+  #[test]
+  fn formats_backup_timestamp() {
+    assert_eq!(get_timestamp(0), "19700101-000000");
+    assert_eq!(get_timestamp(951782400), "20000229-000000");
+    assert_eq!(get_timestamp(1760788799), "20251018-115959");
+  }
+}
@@ -2,16 +2,22 @@ use dirs;
 use std::{ include_bytes };
 
 use std::path::{ PathBuf };
+use std::collections::{ BTreeMap };
 use std::fs;
-use std::fs::{ File };
-use std::io::{ BufReader, BufWriter };
 
 use gtk::glib::{ Bytes };
 
 use serde::{Deserialize, Serialize};
+use serde_json::{ Value };
 
-use regex::Regex;
+use url::{ Url };
 
+use crate::routing;
+use crate::sourceapp::{ SourceApp };
+use format::{ ConfigFormat };
+
+mod format;
+mod migrations;
 #[cfg(target_family = "unix")] mod unix;
 #[cfg(target_family = "windows")] mod windows;
 
@@ -21,6 +27,8 @@ use regex::Regex;
 #[cfg(target_family = "unix")] pub static BW_EXECUTABLE:&str = "browsewith";
 #[cfg(target_family = "unix")] pub static BW_DOTDESKTOP:&str = "browsewith.desktop";
 pub static BW_CONFIG:&str = "config.json";
+pub static BW_CONFIG_TOML:&str = "config.toml";
+pub static BW_CONFIG_YAML:&str = "config.yaml";
 pub static BW_ICON_APPLICATION:&str = "browsewith.ico";
 #[cfg(target_family = "windows")] pub static BW_ICON_CLOSE:&str = "close.png";
 
@@ -32,16 +40,18 @@ pub static BW_ICON_APPLICATION:&str = "browsewith.ico";
 #[cfg(target_os = "freebsd")] pub static PATH_DESKTOP:&str = "/usr/local/share/applications";
 #[cfg(target_os = "freebsd")] pub static PATH_ICON:&str = "/usr/local/share/icons/hicolor/scalable/apps";
 
-#[derive(Debug, PartialEq, Clone, Copy, Serialize, Deserialize)]
+// Actions are ordered from the least to the most restrictive
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Clone, Copy, Serialize, Deserialize)]
 pub enum CharsetPolicyAction {
   Allow,
+  #[serde(alias = "Confirm")]
   Warn,
   Block
 }
 
-#[derive(Debug, PartialEq)]
+#[derive(Debug, PartialEq, Clone, Copy)]
 pub enum CharsetList {
-  Unknown,
+  Utf8,
   Utf16,
   Utf32
 }
@@ -61,13 +71,80 @@ pub struct ButtonProperties {
 pub struct WindowProperties {
   pub always_ontop: bool,
   pub position: String,
+  pub timeout_seconds: Option<u32>,
+  pub default_browser: Option<String>
 }
 
 #[derive(Clone, Copy, Serialize, Deserialize)]
 pub struct CharsetPolicy {
   pub utf8:CharsetPolicyAction,
   pub utf16:CharsetPolicyAction,
-  pub utf32:CharsetPolicyAction
+  pub utf32:CharsetPolicyAction,
+  pub idn:Option<CharsetPolicyAction>
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct Redirector {
+  pub name: String,
+  pub host: String,
+  pub path: String,
+  pub parameter: String
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct RedirectPolicy {
+  pub unwrap: bool,
+  pub redirectors: Vec<Redirector>
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct TrackingPolicy {
+  pub strip: bool,
+  pub parameters: Vec<String>,
+  pub exceptions: Vec<String>
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct ShortLinkPolicy {
+  pub expand: bool,
+  pub domains: Vec<String>,
+  pub max_redirects: u32,
+  pub timeout: u64
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct FileHandler {
+  pub mime_type: String,
+  pub executable: String,
+  pub arguments: String
+}
+
+#[derive(Debug, PartialEq, Clone, Copy, Serialize, Deserialize)]
+pub enum DenylistPatternType {
+  Exact,
+  Wildcard,
+  Regex
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct DenylistRule {
+  pub name: String,
+  pub pattern: String,
+  pub pattern_type: DenylistPatternType,
+  pub action: CharsetPolicyAction
+}
+
+#[derive(Clone, Copy, Serialize, Deserialize)]
+pub struct ThreatPolicy {
+  pub check: bool,
+  pub action: CharsetPolicyAction
+}
+
+#[derive(Clone, Default, Serialize, Deserialize)]
+pub struct IntranetPolicy {
+  pub suffixes: Vec<String>,
+  pub resolve: bool,
+  pub timeout: u64
 }
 
 #[derive(Serialize, Deserialize)]
@@ -76,25 +153,118 @@ pub struct Settings {
   pub host_info: bool,
   pub buttons: ButtonProperties,
   pub window: WindowProperties,
-  pub charset_policy: Option<CharsetPolicy>
+  pub charset_policy: Option<CharsetPolicy>,
+  pub redirect_policy: Option<RedirectPolicy>,
+  pub tracking_policy: Option<TrackingPolicy>,
+  pub schemes: Option<Vec<String>>,
+  pub shortlink_policy: Option<ShortLinkPolicy>,
+  pub file_handlers: Option<Vec<FileHandler>>,
+  pub denylist: Option<Vec<DenylistRule>>,
+  pub threat_policy: Option<ThreatPolicy>,
+  pub intranet: Option<IntranetPolicy>
 }
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct BrowserSettings {
   pub title: String,
+  pub id: Option<String>,
   pub executable: String,
   pub arguments: String,
   pub icon: String,
-  pub auto_launch: Option<Vec<String>>
+  pub auto_launch: Option<Vec<AutoLaunch>>,
+  pub fallback: Option<Vec<String>>
+}
+
+// 'auto_launch' entries are either a URL pattern, or an object with the URL pattern
+// and the application that opened the URL
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum AutoLaunch {
+  Pattern(String),
+  Conditions {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    url: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    source_app: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    arguments: Option<Vec<String>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    environment: Option<BTreeMap<String, String>>
+  }
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct RewriteRule {
+  pub pattern: String,
+  pub replacement: String
+}
+
+// Conditions are written as '{ "host": "*.example.com" }' or '{ "all": [ ... ] }'
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Condition {
+  Scheme(String),
+  Host(String),
+  Path(String),
+  QueryKey(String),
+  Port(u16),
+  Url(String),
+  SourceApp(String),
+  Intranet(bool),
+  All(Vec<Condition>),
+  Any(Vec<Condition>),
+  Not(Box<Condition>)
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Rule {
+  pub name: String,
+  pub priority: i32,
+  pub browser: String,
+  pub condition: Condition,
+  // Rules added by 'Remember my choice' in the main window
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub remembered: Option<bool>,
+  // Added to the browser arguments and environment when the rule opens the URL
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub arguments: Option<Vec<String>>,
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub environment: Option<BTreeMap<String, String>>
+}
+
+impl Rule {
+  // Returns the extra arguments and environment variables, or an empty string if there are none
+  pub fn get_launch_description(&self) -> String {
+    let mut description:Vec<String> = vec![];
+
+    match self.arguments {
+      Some(ref arguments) if arguments.len() > 0 => { description.push(format!("arguments: {}", arguments.join(" "))); },
+      _ => { }
+    }
+    match self.environment {
+      Some(ref environment) if environment.len() > 0 => {
+        let variables:Vec<String> = environment.iter().map( |(name, value)| format!("{}={}", name, value) ).collect();
+        description.push(format!("environment: {}", variables.join(" ")));
+      },
+      _ => { }
+    }
+
+    return description.join(", ");
+  }
 }
 
 #[derive(Serialize, Deserialize)]
 pub struct Configuration {
+  pub version: u64,
   pub settings: Settings,
-  pub browsers_list: Vec<BrowserSettings>
+  pub browsers_list: Vec<BrowserSettings>,
+  pub rewrite_rules: Option<Vec<RewriteRule>>,
+  pub rules: Option<Vec<Rule>>,
+  #[serde(skip)]
+  pub rule_set: routing::RuleSet
 }
 
-pub fn get_configuration() -> Configuration {
+pub fn get_configuration() -> Result<Configuration, String> {
   let mut configuration:Configuration;
   let home_dir_path:Option<PathBuf>;
   let config_directory_buf:PathBuf;
@@ -120,10 +290,15 @@ pub fn get_configuration() -> Configuration {
     create_configuration_file(&config_file_buf);
   }
 
-  configuration = load_configuration(&config_file_buf);
-  configuration = upgrade_configuration(configuration);
+  configuration = load_configuration(&config_file_buf)?;
+
+  // Compile the routing rules once, reporting the invalid patterns instead of failing on each URL
+  configuration.rule_set = routing::RuleSet::new(&configuration);
+  if configuration.rule_set.errors.len() > 0 {
+    eprintln!("{}", configuration.rule_set.get_report());
+  }
 
-  return configuration;
+  return Ok(configuration);
 }
 
 pub fn get_home_dir() -> PathBuf {
@@ -154,11 +329,21 @@ pub fn get_config_file() -> PathBuf {
   let mut config_file:PathBuf;
 
   config_file = get_config_dir().to_path_buf();
-  config_file.push("config.json");
+  config_file.push(get_config_file_name(&config_file));
 
   return config_file.to_path_buf();
 }
 
+// 'config.toml' or 'config.yaml' are used instead of 'config.json' when present
+pub fn get_config_file_name(config_dir:&PathBuf) -> &'static str {
+  for file_name in [BW_CONFIG_TOML, BW_CONFIG_YAML] {
+    if config_dir.join(file_name).is_file() {
+      return file_name;
+    }
+  }
+  return BW_CONFIG;
+}
+
 pub fn get_resource_path(dir:&str, file:&str) -> PathBuf {
   let mut path:PathBuf;
   path = get_config_dir().to_path_buf();
@@ -176,8 +361,6 @@ fn create_configuration_file(config_full_path:&PathBuf) {
 fn get_default_settings() -> Configuration {
   let mut default_settings:Configuration;
   let installed_browsers:Vec<BrowserSettings>;
-  let config_raw:&[u8];
-  let config_bytes:Bytes;
 
   #[cfg(target_family = "unix")] {
     installed_browsers = unix::get_browser_list();
@@ -186,55 +369,161 @@ fn get_default_settings() -> Configuration {
     installed_browsers = windows::get_browser_list();
   }
 
-  config_raw = include_bytes!("../../resources/config.json");
-  config_bytes = Bytes::from(config_raw);
-  default_settings = serde_json::from_slice(&config_bytes).unwrap();
+  default_settings = load_default_configuration();
   default_settings.browsers_list = installed_browsers;
 
   return default_settings;
 }
 
-fn load_configuration(file_path:&PathBuf) -> Configuration {
-  let reader:BufReader<File>;
-  let file_handle:File;
-  let configuration:Configuration;
+fn load_default_configuration() -> Configuration {
+  return serde_json::from_value(load_default_document()).unwrap();
+}
+
+fn load_default_document() -> Value {
+  let config_raw:&[u8];
+  let config_bytes:Bytes;
+
+  config_raw = include_bytes!("../../resources/config.json");
+  config_bytes = Bytes::from(config_raw);
+  return serde_json::from_slice(&config_bytes).unwrap();
+}
+
+// URL schemes accepted by BrowseWith. This only reads the file, without creating or upgrading it,
+// as it's used by every command including installing and uninstalling. Any error in the file
+// falls back to the default schemes.
+pub fn get_supported_schemes() -> Vec<String> {
+  let config_file_buf:PathBuf = get_config_file();
+  let mut schemes:Option<Vec<String>> = None;
+
+  if config_file_buf.is_file() {
+    schemes = match read_configuration_document(&config_file_buf) {
+      Ok(document) => serde_json::from_value(document["settings"]["schemes"].clone()).unwrap_or(None),
+      Err(..) => None
+    };
+  }
+  if schemes.is_none() {
+    schemes = load_default_configuration().settings.schemes;
+  }
+
+  return schemes.unwrap_or(vec![]).iter().map( |s| s.to_lowercase() ).collect();
+}
+
+fn load_configuration(file_path:&PathBuf) -> Result<Configuration, String> {
+  let mut document:Value;
 
-  file_handle = File::open(file_path).unwrap();
-  reader = BufReader::new(file_handle);
-  configuration = serde_json::from_reader(reader).unwrap();
-  return configuration;
+  document = read_configuration_document(file_path)?;
+  match upgrade_configuration(file_path, &mut document) {
+    Ok(..) => { },
+    Err(e) => { println!("ERROR: Unable to upgrade {}: {}", file_path.to_str().unwrap(), e); }
+  }
+  return serde_json::from_value(document).map_err( |e| format!("Invalid configuration file '{}': {}", file_path.display(), e) );
+}
+
+// Load a configuration file without creating or saving it, with the routing rules compiled.
+// Files written by older versions are migrated in memory.
+pub fn load_configuration_file(file_path:&PathBuf) -> Result<Configuration, String> {
+  let mut document:Value;
+  let mut configuration:Configuration;
+
+  document = read_configuration_document(file_path)?;
+  migrations::migrate(&mut document, &load_default_document()).map_err( |e| format!("Invalid configuration file '{}': {}", file_path.display(), e) )?;
+  configuration = serde_json::from_value(document).map_err( |e| format!("Invalid configuration file '{}': {}", file_path.display(), e) )?;
+  configuration.rule_set = routing::RuleSet::new(&configuration);
+
+  return Ok(configuration);
+}
+
+// Configuration files in 'tests/fixtures/config', shared by the tests of the submodules
+#[cfg(test)]
+fn load_test_fixture(name:&str) -> Value {
+  let mut fixture:PathBuf = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+  fixture.push("tests/fixtures/config");
+  fixture.push(name);
+  return read_configuration_document(&fixture).unwrap();
+}
+
+// JSON, TOML or YAML, depending on the file extension
+fn read_configuration_document(file_path:&PathBuf) -> Result<Value, String> {
+  let contents:String;
+
+  contents = fs::read_to_string(file_path).map_err( |e| format!("Unable to read '{}': {}", file_path.display(), e) )?;
+  return ConfigFormat::from_path(file_path).parse(&contents).map_err( |e| format!("Invalid configuration file '{}': {}", file_path.display(), e) );
 }
 
-fn save_configuration(file_path:&PathBuf, data:&Configuration) {
-  let writer:BufWriter<File>;
-  let file_handle:File;
+// The file is written in the format of its extension, so that the user's choice is kept
+fn save_configuration<T:Serialize>(file_path:&PathBuf, data:&T) {
+  let contents:Result<String, String>;
 
-  file_handle = File::create(file_path).unwrap();
-  writer = BufWriter::new(file_handle);
-  match serde_json::to_writer_pretty(writer, &data) {
-    Ok(..) => { println!("Created default configuration: {}", file_path.to_str().unwrap()); },
+  contents = serde_json::to_value(data).map_err( |e| e.to_string() )
+    .and_then( |document| ConfigFormat::from_path(file_path).serialize(&document) );
+  match contents.and_then( |contents| fs::write(file_path, contents).map_err( |e| e.to_string() ) ) {
+    Ok(..) => { println!("Saved configuration: {}", file_path.to_str().unwrap()); },
     Err(..) => { println!("Failed to create {}", file_path.to_str().unwrap()); }
   };
 }
 
-pub fn upgrade_configuration(mut data:Configuration) -> Configuration {
+// Migrate files written by older versions to the current format, sections added since then
+// are copied from the default configuration. The original file is kept as a backup.
+pub fn upgrade_configuration(file_path:&PathBuf, document:&mut Value) -> Result<(), String> {
+  let backup_file:PathBuf;
+
+  if !migrations::migrate(document, &load_default_document())? {
+    return Ok(());
+  }
+
+  backup_file = migrations::get_backup_file(file_path);
+  match fs::copy(file_path, &backup_file) {
+    Ok(..) => { println!("Saved configuration backup: {}", backup_file.to_str().unwrap()); },
+    Err(e) => { return Err(format!("failed to create the backup {}: {}", backup_file.to_str().unwrap(), e)); }
+  }
+  save_configuration(file_path, document);
+
+  return Ok(());
+}
+
+// Write the user configuration in another format. The new file is read back to check that
+// nothing was lost, and the previous file is kept as a backup so that the new one is used.
+pub fn convert_configuration(format_name:&str) -> Result<PathBuf, String> {
   let config_file_buf:PathBuf = get_config_file();
-  let default_settings:Configuration = get_default_settings();
-  let mut config_upgraded = false;
-
-  match data.settings.charset_policy {
-    Some(_) => { },
-    None => {
-      data.settings.charset_policy = default_settings.settings.charset_policy;
-      config_upgraded = true;
-    }
+  let format:ConfigFormat;
+  let document:Value;
+  let contents:String;
+  let mut new_file_buf:PathBuf;
+  let backup_file:PathBuf;
+
+  format = match ConfigFormat::from_name(format_name) {
+    Some(format) => format,
+    None => { return Err(format!("Unknown format '{}', use json, toml or yaml", format_name)); }
+  };
+  if !config_file_buf.is_file() {
+    return Err(format!("The configuration file {} doesn't exist", config_file_buf.display()));
+  }
+  if ConfigFormat::from_path(&config_file_buf) == format {
+    return Err(format!("The configuration file {} is already in this format", config_file_buf.display()));
+  }
+  new_file_buf = get_config_dir();
+  new_file_buf.push(format.get_file_name());
+  if new_file_buf.exists() {
+    return Err(format!("{} already exists", new_file_buf.display()));
   }
 
-  if config_upgraded {
-    save_configuration(&config_file_buf, &data);
+  document = read_configuration_document(&config_file_buf)?;
+  contents = format.serialize(&document)?;
+  if !format::is_same_document(&format.parse(&contents)?, &document) {
+    return Err(format!("The configuration can't be converted to {:?} without changes", format));
   }
 
-  return data;
+  fs::write(&new_file_buf, contents).map_err( |e| format!("Failed to create {}: {}", new_file_buf.display(), e) )?;
+  backup_file = migrations::get_backup_file(&config_file_buf);
+  match fs::rename(&config_file_buf, &backup_file) {
+    Ok(..) => { println!("Saved configuration backup: {}", backup_file.display()); },
+    Err(e) => {
+      let _ = fs::remove_file(&new_file_buf);
+      return Err(format!("Failed to create the backup {}: {}", backup_file.display(), e));
+    }
+  }
+
+  return Ok(new_file_buf);
 }
 
 #[cfg(target_family = "windows")]
@@ -283,20 +572,140 @@ pub fn get_lib_path(is_admin:bool) -> PathBuf {
   return windows::get_lib_path(is_admin);
 }
 
-pub fn auto_launch_browser(url: String, browser_settings: Vec<BrowserSettings>) -> Option<BrowserSettings> {
-  let mut re;
-  for browser in browser_settings {
-    match browser.auto_launch {
-      Some(ref auto_launch_url) => {
-        for config_url in auto_launch_url {
-          re = Regex::new(config_url).unwrap();
-          if re.is_match(&url) {
-            return Some(browser);
-          }
-        }
-      },
-      None => { }
+// Returns the browser that opens the URL without displaying the main window, and the rule that selected it
+pub fn auto_launch_browser(url:&str, configuration:&Configuration, source_app:&Option<SourceApp>) -> Option<(Rule, BrowserSettings)> {
+  // URLs in the denylist are always confirmed by the user, never opened automatically
+  if get_denylist_rule(url, configuration).is_some() {
+    return None;
+  }
+  return routing::get_matching_rule(url, configuration, source_app);
+}
+
+// Returns the first denylist rule matching the URL host, the rules are compiled with the routing rules
+pub fn get_denylist_rule(url:&str, configuration:&Configuration) -> Option<DenylistRule> {
+  return routing::get_denylist_rule(url, &configuration.rule_set);
+}
+
+// Add a rule to open the URL host with the browser, replacing the previous choice for the host.
+// The rules are changed in the file as it is, so the rest of the user's file isn't rewritten.
+pub fn remember_site(url:&str, browser:&BrowserSettings) {
+  let config_file_buf:PathBuf = get_config_file();
+  let mut document:Value;
+  let rule:Rule;
+  let host:String;
+
+  host = match Url::parse(url) {
+    Ok(url) => url.host_str().unwrap_or("").to_lowercase(),
+    Err(..) => { return; }
+  };
+  if host == "" {
+    return;
+  }
+
+  document = match read_rules_document(&config_file_buf) {
+    Ok(document) => document,
+    Err(e) => {
+      println!("ERROR: Unable to remember the site: {}", e);
+      return;
     }
+  };
+  rule = Rule {
+    name: format!("Remembered {}", host),
+    priority: 0,
+    browser: routing::get_browser_reference(browser),
+    condition: Condition::Host(host.clone()),
+    remembered: Some(true),
+    arguments: None,
+    environment: None
+  };
+  match document["rules"].as_array_mut() {
+    Some(rules) => {
+      rules.retain( |rule| get_remembered_rule_host(rule) != Some(host.clone()) );
+      rules.push(serde_json::to_value(rule).unwrap());
+    },
+    None => { }
+  }
+
+  save_configuration(&config_file_buf, &document);
+}
+
+// Returns the remembered rules, if any
+pub fn get_remembered_sites() -> Vec<Rule> {
+  let config_file_buf:PathBuf = get_config_file();
+
+  if !config_file_buf.is_file() {
+    return vec![];
+  }
+  match read_configuration_document(&config_file_buf) {
+    Ok(document) => {
+      return document["rules"].as_array().unwrap_or(&vec![]).iter()
+        .filter_map( |rule| serde_json::from_value::<Rule>(rule.clone()).ok() )
+        .filter( |rule| get_remembered_host(rule).is_some() )
+        .collect();
+    },
+    Err(..) => { return vec![]; }
+  }
+}
+
+// Remove the remembered choice for the host, returns false if there wasn't one
+pub fn forget_site(host:&str) -> bool {
+  let config_file_buf:PathBuf = get_config_file();
+  let mut document:Value;
+  let host:String = host.to_lowercase();
+
+  if !config_file_buf.is_file() {
+    return false;
+  }
+  document = match read_rules_document(&config_file_buf) {
+    Ok(document) => document,
+    Err(e) => {
+      println!("ERROR: Unable to forget the site: {}", e);
+      return false;
+    }
+  };
+  match document["rules"].as_array_mut() {
+    Some(rules) => {
+      let rules_count:usize = rules.len();
+      rules.retain( |rule| get_remembered_rule_host(rule) != Some(host.clone()) );
+      if rules.len() == rules_count {
+        return false;
+      }
+    },
+    None => { return false; }
+  }
+
+  save_configuration(&config_file_buf, &document);
+  return true;
+}
+
+// Configuration document with the 'rules' list, which is added if the file doesn't have one
+fn read_rules_document(file_path:&PathBuf) -> Result<Value, String> {
+  let mut document:Value = read_configuration_document(file_path)?;
+
+  if !document.is_object() {
+    return Err(format!("Invalid configuration file '{}'", file_path.display()));
+  }
+  if document["rules"].is_null() {
+    document["rules"] = Value::Array(vec![]);
+  }
+  if !document["rules"].is_array() {
+    return Err(format!("'rules' isn't a list in '{}'", file_path.display()));
+  }
+
+  return Ok(document);
+}
+
+// Host of a remembered rule in the configuration document, rules that can't be read aren't remembered
+fn get_remembered_rule_host(rule:&Value) -> Option<String> {
+  match serde_json::from_value::<Rule>(rule.clone()) {
+    Ok(rule) => { return get_remembered_host(&rule); },
+    Err(..) => { return None; }
+  }
+}
+
+pub fn get_remembered_host(rule:&Rule) -> Option<String> {
+  match (rule.remembered, &rule.condition) {
+    (Some(true), Condition::Host(host)) => { return Some(host.to_string()); },
+    _ => { return None; }
   }
-  return std::option::Option::None;
 }
@@ -3,10 +3,10 @@ use std::fs::{ read_to_string };
 use std::str::{ Lines };
 
 use crate::config::{ BrowserSettings };
-use crate::config::{ get_home_dir };
+use crate::config::{ get_home_dir, get_config_file_name };
 use crate::config::{
   PATH_EXECUTABLE, PATH_DESKTOP, PATH_ICON,
-  BW_EXECUTABLE, BW_CONFIG, BW_ICON_APPLICATION, BW_DOTDESKTOP
+  BW_EXECUTABLE, BW_ICON_APPLICATION, BW_DOTDESKTOP
 };
 
 pub fn get_browser_list() -> Vec<BrowserSettings> {
@@ -18,17 +18,17 @@ pub fn get_browser_list() -> Vec<BrowserSettings> {
   let mut browsers_settings:BrowserSettings;
 
   let mut browser_list:Vec<BrowserSettings> = [
-    BrowserSettings { title: "_Brave".to_string(), executable: "brave-browser".to_string(), arguments: "".to_string(), icon: "brave-browser.png".to_string(), auto_launch: None },
-    BrowserSettings { title: "Brave Incog_nito".to_string(), executable: "brave-browser".to_string(), arguments: "--incognito".to_string(), icon: "brave-browser.png".to_string(), auto_launch: None },
-    BrowserSettings { title: "Brave _TOR".to_string(), executable: "brave-browser".to_string(), arguments: "--tor".to_string(), icon: "brave-browser.png".to_string(), auto_launch: None },
-    BrowserSettings { title: "_Edge".to_string(), executable: "Microsoft\\Edge\\Application\\msedge.exe".to_string(), arguments: "--tor".to_string(), icon: "".to_string(), auto_launch: None },
-    BrowserSettings { title: "Edge In_Private".to_string(), executable: "Microsoft\\Edge\\Application\\msedge.exe".to_string(), arguments: "--tor".to_string(), icon: "".to_string(), auto_launch: None },
-    BrowserSettings { title: "_Firefox".to_string(), executable: "firefox".to_string(), arguments: "-new-tab".to_string(), icon: "firefox.png".to_string(), auto_launch: None },
-    BrowserSettings { title: "Firefox Pri_vate".to_string(), executable: "firefox".to_string(), arguments: "-private-window".to_string(), icon: "firefox.png".to_string(), auto_launch: None },
-    BrowserSettings { title: "_Google".to_string(), executable: "google-chrome".to_string(), arguments: "".to_string(), icon: "google-chrome.png".to_string(), auto_launch: None },
-    BrowserSettings { title: "Google _Incognito".to_string(), executable: "google-chrome".to_string(), arguments: "--incognito".to_string(), icon: "google-chrome.png".to_string(), auto_launch: None },
-    BrowserSettings { title: "_Chromium".to_string(), executable: "chromium-browser".to_string(), arguments: "".to_string(), icon: "chromium-browser.png".to_string(), auto_launch: None },
-    BrowserSettings { title: "Chromium Incognit_o".to_string(), executable: "chromium-browser".to_string(), arguments: "--incognito".to_string(), icon: "chromium-browser.png".to_string(), auto_launch: None },
+    BrowserSettings { title: "_Brave".to_string(), id: None, executable: "brave-browser".to_string(), arguments: "".to_string(), icon: "brave-browser.png".to_string(), auto_launch: None, fallback: None },
+    BrowserSettings { title: "Brave Incog_nito".to_string(), id: None, executable: "brave-browser".to_string(), arguments: "--incognito".to_string(), icon: "brave-browser.png".to_string(), auto_launch: None, fallback: None },
+    BrowserSettings { title: "Brave _TOR".to_string(), id: None, executable: "brave-browser".to_string(), arguments: "--tor".to_string(), icon: "brave-browser.png".to_string(), auto_launch: None, fallback: None },
+    BrowserSettings { title: "_Edge".to_string(), id: None, executable: "Microsoft\\Edge\\Application\\msedge.exe".to_string(), arguments: "--tor".to_string(), icon: "".to_string(), auto_launch: None, fallback: None },
+    BrowserSettings { title: "Edge In_Private".to_string(), id: None, executable: "Microsoft\\Edge\\Application\\msedge.exe".to_string(), arguments: "--tor".to_string(), icon: "".to_string(), auto_launch: None, fallback: None },
+    BrowserSettings { title: "_Firefox".to_string(), id: None, executable: "firefox".to_string(), arguments: "-new-tab".to_string(), icon: "firefox.png".to_string(), auto_launch: None, fallback: None },
+    BrowserSettings { title: "Firefox Pri_vate".to_string(), id: None, executable: "firefox".to_string(), arguments: "-private-window".to_string(), icon: "firefox.png".to_string(), auto_launch: None, fallback: None },
+    BrowserSettings { title: "_Google".to_string(), id: None, executable: "google-chrome".to_string(), arguments: "".to_string(), icon: "google-chrome.png".to_string(), auto_launch: None, fallback: None },
+    BrowserSettings { title: "Google _Incognito".to_string(), id: None, executable: "google-chrome".to_string(), arguments: "--incognito".to_string(), icon: "google-chrome.png".to_string(), auto_launch: None, fallback: None },
+    BrowserSettings { title: "_Chromium".to_string(), id: None, executable: "chromium-browser".to_string(), arguments: "".to_string(), icon: "chromium-browser.png".to_string(), auto_launch: None, fallback: None },
+    BrowserSettings { title: "Chromium Incognit_o".to_string(), id: None, executable: "chromium-browser".to_string(), arguments: "--incognito".to_string(), icon: "chromium-browser.png".to_string(), auto_launch: None, fallback: None },
     // { title: "".to_string(), executable: "".to_string(), arguments: "--tor".to_string(), icon: "".to_string() },
   ].to_vec();
 
@@ -136,6 +136,6 @@ pub fn get_configuration_path() -> PathBuf {
 pub fn get_configuration_file() -> PathBuf {
   let mut file:PathBuf;
   file = get_configuration_path();
-  file.push(BW_CONFIG);
+  file.push(get_config_file_name(&file));
   return file;
 }
@@ -1,8 +1,8 @@
 use std::path::{ PathBuf };
 
 use crate::config::{ BrowserSettings };
-use crate::config::{ get_home_dir };
-use crate::config::{ BW_EXECUTABLE, BW_CONFIG, BW_ICON_APPLICATION };
+use crate::config::{ get_home_dir, get_config_file_name };
+use crate::config::{ BW_EXECUTABLE, BW_ICON_APPLICATION };
 
 pub fn get_browser_list() -> Vec<BrowserSettings> {
   let program_files_list:[String; 2];
@@ -15,17 +15,17 @@ pub fn get_browser_list() -> Vec<BrowserSettings> {
   let mut icon_index:i32;
 
   let mut browser_list:Vec<BrowserSettings> = [
-    BrowserSettings { title: "_Brave".to_string(), executable: "BraveSoftware\\Brave-Browser\\Application\\brave.exe,0".to_string(), arguments: "".to_string(), icon: "".to_string(), auto_launch: None },
-    BrowserSettings { title: "Brave Incog_nito".to_string(), executable: "BraveSoftware\\Brave-Browser\\Application\\brave.exe,0".to_string(), arguments: "--incognito".to_string(), icon: "".to_string(), auto_launch: None },
-    BrowserSettings { title: "Brave _TOR".to_string(), executable: "BraveSoftware\\Brave-Browser\\Application\\brave.exe,0".to_string(), arguments: "--tor".to_string(), icon: "".to_string(), auto_launch: None },
-    BrowserSettings { title: "_Edge".to_string(), executable: "Microsoft\\Edge\\Application\\msedge.exe,0".to_string(), arguments: "".to_string(), icon: "".to_string(), auto_launch: None },
-    BrowserSettings { title: "Edge In_Private".to_string(), executable: "Microsoft\\Edge\\Application\\msedge.exe,0".to_string(), arguments: "-inprivate".to_string(), icon: "".to_string(), auto_launch: None },
-    BrowserSettings { title: "_Firefox".to_string(), executable: "Mozilla Firefox\\firefox.exe,0".to_string(), arguments: "-new-tab".to_string(), icon: "".to_string(), auto_launch: None },
-    BrowserSettings { title: "Firefox Pri_vate".to_string(), executable: "Mozilla Firefox\\firefox.exe,4".to_string(), arguments: "-private-window".to_string(), icon: "".to_string(), auto_launch: None },
-    BrowserSettings { title: "_Google".to_string(), executable: "Google\\Chrome\\Application\\chrome.exe,0".to_string(), arguments: "".to_string(), icon: "".to_string(), auto_launch: None },
-    BrowserSettings { title: "Google _Incognito".to_string(), executable: "Google\\Chrome\\Application\\chrome.exe,7".to_string(), arguments: "--incognito".to_string(), icon: "".to_string(), auto_launch: None },
-    BrowserSettings { title: "Internet E_xplorer".to_string(), executable: "Internet Explorer\\iexplore.exe,0".to_string(), arguments: "".to_string(), icon: "".to_string(), auto_launch: None },
-    BrowserSettings { title: "Internet Explorer InPrivate".to_string(), executable: "Internet Explorer\\iexplore.exe,0".to_string(), arguments: "-private".to_string(), icon: "".to_string(), auto_launch: None },
+    BrowserSettings { title: "_Brave".to_string(), id: None, executable: "BraveSoftware\\Brave-Browser\\Application\\brave.exe,0".to_string(), arguments: "".to_string(), icon: "".to_string(), auto_launch: None, fallback: None },
+    BrowserSettings { title: "Brave Incog_nito".to_string(), id: None, executable: "BraveSoftware\\Brave-Browser\\Application\\brave.exe,0".to_string(), arguments: "--incognito".to_string(), icon: "".to_string(), auto_launch: None, fallback: None },
+    BrowserSettings { title: "Brave _TOR".to_string(), id: None, executable: "BraveSoftware\\Brave-Browser\\Application\\brave.exe,0".to_string(), arguments: "--tor".to_string(), icon: "".to_string(), auto_launch: None, fallback: None },
+    BrowserSettings { title: "_Edge".to_string(), id: None, executable: "Microsoft\\Edge\\Application\\msedge.exe,0".to_string(), arguments: "".to_string(), icon: "".to_string(), auto_launch: None, fallback: None },
+    BrowserSettings { title: "Edge In_Private".to_string(), id: None, executable: "Microsoft\\Edge\\Application\\msedge.exe,0".to_string(), arguments: "-inprivate".to_string(), icon: "".to_string(), auto_launch: None, fallback: None },
+    BrowserSettings { title: "_Firefox".to_string(), id: None, executable: "Mozilla Firefox\\firefox.exe,0".to_string(), arguments: "-new-tab".to_string(), icon: "".to_string(), auto_launch: None, fallback: None },
+    BrowserSettings { title: "Firefox Pri_vate".to_string(), id: None, executable: "Mozilla Firefox\\firefox.exe,4".to_string(), arguments: "-private-window".to_string(), icon: "".to_string(), auto_launch: None, fallback: None },
+    BrowserSettings { title: "_Google".to_string(), id: None, executable: "Google\\Chrome\\Application\\chrome.exe,0".to_string(), arguments: "".to_string(), icon: "".to_string(), auto_launch: None, fallback: None },
+    BrowserSettings { title: "Google _Incognito".to_string(), id: None, executable: "Google\\Chrome\\Application\\chrome.exe,7".to_string(), arguments: "--incognito".to_string(), icon: "".to_string(), auto_launch: None, fallback: None },
+    BrowserSettings { title: "Internet E_xplorer".to_string(), id: None, executable: "Internet Explorer\\iexplore.exe,0".to_string(), arguments: "".to_string(), icon: "".to_string(), auto_launch: None, fallback: None },
+    BrowserSettings { title: "Internet Explorer InPrivate".to_string(), id: None, executable: "Internet Explorer\\iexplore.exe,0".to_string(), arguments: "-private".to_string(), icon: "".to_string(), auto_launch: None, fallback: None },
   ].to_vec();
 
   program_files_list = [
@@ -126,7 +126,7 @@ pub fn get_configuration_path() -> PathBuf {
 pub fn get_configuration_file() -> PathBuf {
   let mut path:PathBuf;
   path = get_configuration_path();
-  path.push(BW_CONFIG);
+  path.push(get_config_file_name(&path));
   return path;
 }
 
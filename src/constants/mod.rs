@@ -1,2 +1,5 @@
 pub static UPDATES_CHECK_FILENAME:&'static str = "updates.json";
-pub static UPDATES_CHECK_FILE_DELAY:u64 = 604800;
\ No newline at end of file
+pub static UPDATES_CHECK_FILE_DELAY:u64 = 604800;
+pub static REDIRECTS_MAX_DEPTH:usize = 5;
+pub static THREATLIST_FILENAME:&'static str = "threatlist.txt";
+pub static BROWSER_START_GRACE_PERIOD:u64 = 1000;
\ No newline at end of file
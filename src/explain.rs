@@ -0,0 +1,166 @@
+use crate::config;
+use crate::config::{ CharsetPolicyAction, Configuration, Rule };
+use crate::charset;
+use crate::localfile;
+use crate::routing;
+use crate::sourceapp::{ SourceApp };
+use crate::threatlist;
+use crate::webclient;
+
+// Run the URL through the same steps used when opening it, printing the result of each
+// step and the browser that would be selected. Nothing is launched and no dialog is displayed.
+pub fn explain_url(request_url:&str, configuration:&Configuration, supported_schemes:&Vec<String>, source_app:&Option<SourceApp>) {
+  let mut url:String = request_url.to_string();
+  let mut chooser_required:bool = false;
+  let mut previous_url:String;
+
+  println!("URL: {}", url);
+
+  // Local files
+  match localfile::LocalFile::new(&url) {
+    Some(local_file) => {
+      println!("Local file: '{}', type '{}'", local_file.path.display(), local_file.mime_type);
+      if !local_file.is_browser_file() {
+        match local_file.get_handler(&configuration.settings.file_handlers) {
+          Some(handler) => { println!("Result: opened with the file handler '{}'", handler.executable); },
+          None => { println!("Result: rejected, there isn't a file handler for '{}'", local_file.mime_type); }
+        }
+        return;
+      }
+      url = local_file.url;
+      print_step("Converted to", &url, "");
+    },
+    None => {
+      if !webclient::validate_url(&url, supported_schemes) {
+        println!("Result: rejected, not a valid URL or the scheme isn't in 'schemes' ({})", supported_schemes.join(", "));
+        return;
+      }
+      println!("Valid URL: yes");
+    }
+  }
+
+  // URL transformations, in the same order as when opening the URL
+  previous_url = url.clone();
+  url = webclient::unwrap_url(&url, &configuration.settings.redirect_policy, supported_schemes);
+  print_step("Unwrap redirectors", &url, &previous_url);
+
+  previous_url = url.clone();
+  url = webclient::expand_url(&url, &configuration.settings.shortlink_policy, supported_schemes);
+  print_step("Expand short links", &url, &previous_url);
+
+  if webclient::validate_url(&url, supported_schemes) {
+    previous_url = url.clone();
+    url = webclient::rewrite_url(&url, &configuration.rewrite_rules, supported_schemes);
+    print_step("Rewrite rules", &url, &previous_url);
+  }
+
+  match webclient::strip_tracking_parameters(&url, &configuration.settings.tracking_policy) {
+    Some(stripped_url) => {
+      println!("Tracking parameters: removed {}", stripped_url.parameters.join(", "));
+      url = stripped_url.url;
+      println!("  -> {}", url);
+    },
+    None => { println!("Tracking parameters: none removed"); }
+  }
+
+  // Policies
+  match configuration.settings.charset_policy {
+    Some(ref charset_policy) => {
+      let analysis:charset::UrlAnalysis = charset::analyze_url(&url, charset_policy);
+      println!("Charset policy: {:?}", analysis.action);
+      if analysis.action != CharsetPolicyAction::Allow {
+        println!("  {}", analysis.get_report().replace("\n", "\n  "));
+      }
+      if analysis.action == CharsetPolicyAction::Block {
+        println!("Result: blocked by the charset policy");
+        return;
+      }
+      if analysis.action == CharsetPolicyAction::Warn {
+        println!("  The user is asked to confirm the URL");
+      }
+    },
+    None => {
+      println!("Result: blocked, the charset policy is missing");
+      return;
+    }
+  }
+
+  match config::get_denylist_rule(&url, configuration) {
+    Some(rule) => {
+      println!("Denylist: rule '{}' ({:?})", rule.name, rule.action);
+      if rule.action == CharsetPolicyAction::Block {
+        println!("Result: blocked by the denylist");
+        return;
+      }
+      if rule.action == CharsetPolicyAction::Warn {
+        println!("  The user is asked to confirm the URL, and it is never opened automatically");
+        chooser_required = true;
+      }
+    },
+    None => { println!("Denylist: no match"); }
+  }
+
+  match configuration.settings.threat_policy {
+    Some(policy) if policy.check => {
+      match threatlist::ThreatList::load() {
+        Some(threat_list) => {
+          match threat_list.lookup(&url) {
+            Some(expression) => {
+              println!("Threat list: match '{}' ({:?})", expression, policy.action);
+              if policy.action == CharsetPolicyAction::Block {
+                println!("Result: blocked by the threat list");
+                return;
+              }
+            },
+            None => { println!("Threat list: no match"); }
+          }
+        },
+        None => { println!("Threat list: not installed"); }
+      }
+    },
+    _ => { println!("Threat list: disabled"); }
+  }
+
+  // Routing rules, in evaluation order
+  match source_app {
+    Some(source_app) => { println!("Source application: {}", source_app.get_description()); },
+    None => { println!("Source application: not detected"); }
+  }
+  for error in &configuration.rule_set.errors {
+    println!("Ignored rule: {}", error.get_description());
+  }
+
+  println!("Rules:");
+  let mut selected:Option<(Rule, config::BrowserSettings)> = None;
+  for (index, (rule, is_match)) in routing::get_rule_trace(&url, configuration, source_app).iter().enumerate() {
+    let mut status:String = String::from(if *is_match { "match" } else { "no match" });
+    if *is_match && selected.is_none() {
+      match routing::get_browser(&rule.browser, &configuration.browsers_list) {
+        Some(browser) => {
+          status = String::from("match, selected");
+          selected = Some((rule.clone(), browser));
+        },
+        None => { status = String::from("match, unknown browser"); }
+      }
+    }
+    println!("  {}. [priority {}] {} -> {}: {}", index + 1, rule.priority, rule.name, rule.browser, status);
+  }
+
+  match selected {
+    Some((rule, browser)) if !chooser_required => {
+      println!("Result: opened with '{}' by rule '{}'", browser.title, rule.name);
+      if rule.get_launch_description() != "" {
+        println!("  {}", rule.get_launch_description());
+      }
+    },
+    _ => { println!("Result: the main window is displayed to select a browser"); }
+  }
+}
+
+fn print_step(step:&str, url:&str, previous_url:&str) {
+  if url == previous_url {
+    println!("{}: unchanged", step);
+  } else {
+    println!("{}: {}", step, url);
+  }
+}
@@ -0,0 +1,149 @@
+use url::{ Url };
+use std::path::{ Path, PathBuf };
+use std::fs::{ File };
+use std::io::{ Read };
+
+use crate::config::{ FileHandler };
+
+// MIME types opened with the browsers
+static BROWSER_MIME_TYPES:[&str; 3] = ["text/html", "application/xhtml+xml", "image/svg+xml"];
+
+pub struct LocalFile {
+  pub path: PathBuf,
+  pub url: String,
+  pub mime_type: String
+}
+
+impl LocalFile {
+  // Accepts a path, relative or absolute, or a 'file://' URL
+  pub fn new(location:&str) -> Option<LocalFile> {
+    let path:PathBuf;
+    let absolute_path:PathBuf;
+    let url:Url;
+
+    path = match Url::parse(location) {
+      Ok(url) if url.scheme() == "file" => {
+        match url.to_file_path() {
+          Ok(path) => path,
+          Err(..) => { return None; }
+        }
+      },
+      _ => PathBuf::from(location)
+    };
+    if !path.is_file() {
+      return None;
+    }
+
+    // The browser can have a different working directory, so use the absolute path
+    absolute_path = match path.canonicalize() {
+      Ok(path) => path,
+      Err(..) => { return None; }
+    };
+    url = match Url::from_file_path(&absolute_path) {
+      Ok(url) => url,
+      Err(..) => { return None; }
+    };
+
+    return Some(LocalFile {
+      mime_type: get_mime_type(&absolute_path),
+      path: absolute_path,
+      url: url.to_string()
+    });
+  }
+
+  pub fn is_browser_file(&self) -> bool {
+    return BROWSER_MIME_TYPES.contains(&self.mime_type.as_str());
+  }
+
+  // Handlers can use the full MIME type or a wildcard for the subtype, i.e. 'image/*'
+  pub fn get_handler(&self, file_handlers:&Option<Vec<FileHandler>>) -> Option<FileHandler> {
+    let handlers:&Vec<FileHandler> = match file_handlers {
+      Some(handlers) => handlers,
+      None => { return None; }
+    };
+
+    for handler in handlers {
+      let mime_type:String = handler.mime_type.to_lowercase();
+      if mime_type == self.mime_type {
+        return Some(handler.clone());
+      }
+      if mime_type.ends_with("/*") && self.mime_type.starts_with(mime_type.trim_end_matches("*")) {
+        return Some(handler.clone());
+      }
+    }
+
+    return None;
+  }
+}
+
+// Detect the MIME type from the first bytes of the file, or from the file extension
+// if the content isn't recognised
+pub fn get_mime_type(path:&Path) -> String {
+  match get_mime_type_from_content(path) {
+    Some(mime_type) => { return mime_type.to_string(); },
+    None => { }
+  }
+
+  let extension:String = match path.extension() {
+    Some(extension) => extension.to_string_lossy().to_lowercase(),
+    None => String::new()
+  };
+
+  let mime_type:&str = match extension.as_str() {
+    "html" | "htm" | "shtml" => "text/html",
+    "xhtml" | "xht" => "application/xhtml+xml",
+    "svg" => "image/svg+xml",
+    "txt" | "log" => "text/plain",
+    "xml" => "application/xml",
+    "json" => "application/json",
+    "pdf" => "application/pdf",
+    "png" => "image/png",
+    "jpg" | "jpeg" => "image/jpeg",
+    "gif" => "image/gif",
+    "webp" => "image/webp",
+    "zip" => "application/zip",
+    _ => "application/octet-stream"
+  };
+
+  return mime_type.to_string();
+}
+
+fn get_mime_type_from_content(path:&Path) -> Option<&'static str> {
+  let mut file:File;
+  let mut buffer:[u8; 512] = [0; 512];
+  let size:usize;
+  let text:String;
+
+  file = File::open(path).ok()?;
+  size = file.read(&mut buffer).ok()?;
+
+  let magic_numbers:Vec<(&[u8], &str)> = vec![
+    (b"%PDF-", "application/pdf"),
+    (b"\x89PNG\r\n\x1a\n", "image/png"),
+    (b"\xFF\xD8\xFF", "image/jpeg"),
+    (b"GIF87a", "image/gif"),
+    (b"GIF89a", "image/gif"),
+    (b"PK\x03\x04", "application/zip"),
+    (b"\x7FELF", "application/x-executable"),
+    (b"MZ", "application/x-msdownload")
+  ];
+  for (magic_number, mime_type) in magic_numbers {
+    if buffer[..size].starts_with(magic_number) {
+      return Some(mime_type);
+    }
+  }
+
+  // Text documents, ignoring the byte order mark and leading white space
+  text = String::from_utf8_lossy(&buffer[..size]).trim_start_matches('\u{FEFF}').trim_start().to_lowercase();
+  if text.starts_with("<!doctype html") || text.starts_with("<html") {
+    return Some("text/html");
+  }
+  if text.starts_with("<svg") || (text.starts_with("<?xml") && text.contains("<svg")) {
+    return Some("image/svg+xml");
+  }
+  if text.starts_with("<?xml") && text.contains("<html") {
+    return Some("application/xhtml+xml");
+  }
+
+  return None;
+}
@@ -3,7 +3,7 @@
 
 use gtk::{
   prelude::*,
-  ButtonsType, MessageType, HeaderBar, Application, ApplicationWindow, Button, Image, Box, Orientation, Align, PositionType, Label, WindowPosition, MessageDialog,
+  ButtonsType, MessageType, HeaderBar, Application, ApplicationWindow, Button, CheckButton, Image, Box, Orientation, Align, PositionType, Label, WindowPosition, MessageDialog,
   gio::{ ApplicationFlags },
   pango::{ EllipsizeMode },
   builders::{ ImageBuilder }
@@ -11,8 +11,11 @@ use gtk::{
 use glib::clone;
 
 use std::process::{ Command, Stdio, exit };
-use std::cell::{ RefCell };
+use std::cell::{ Cell, RefCell };
+use std::rc::{ Rc };
 use std::path::{ PathBuf, Path };
+use std::io::{ Read };
+use std::time::{ Duration, Instant };
 
 #[cfg(target_os = "windows")]
 use winapi::um::{
@@ -32,7 +35,14 @@ use std::{ file, line };
 // Add application modules
 mod constants;
 mod config;
+mod charset;
 mod webclient;
+mod localfile;
+mod threatlist;
+mod routing;
+mod sourceapp;
+mod explain;
+mod ruletest;
 mod setup;
 mod update;
 // Windows specific modules
@@ -49,7 +59,12 @@ struct ButtonMargins {
 }
 
 thread_local!(
-  static URL:RefCell<String> = RefCell::new(String::new());
+  static URL:RefCell<Vec<String>> = RefCell::new(vec![]);
+  static STRIPPED_URLS:RefCell<Vec<webclient::StrippedUrl>> = RefCell::new(vec![]);
+  static NO_URL_ARGUMENT:RefCell<bool> = RefCell::new(false);
+  static SOURCE_APP:RefCell<Option<sourceapp::SourceApp>> = RefCell::new(None);
+  static REMEMBER_CHOICE:RefCell<bool> = RefCell::new(false);
+  static RULES:RefCell<Vec<config::Rule>> = RefCell::new(vec![]);
   static ICON_SPACING:RefCell<i32> = RefCell::new(0);
   static GIT_RELEASE:RefCell<update::Releases> = RefCell::new(update::Releases::initialize());
 );
@@ -60,12 +75,16 @@ async fn main() {
 
   let configuration:config::Configuration;
   let mut valid_url:bool;
+  let mut read_clipboard:bool = false;
   let mut error_code:i32;
   let mut argument_list:Vec<String>;
   let argument_count:usize;
   let argument_appname:String;
   let argument_name:String;
-  let mut url_list:String = String::new();
+  let argument_value:String;
+  let argument_config_file:String;
+  let mut url_list:Vec<String> = vec![];
+  let supported_schemes:Vec<String>;
 
   argument_list = std::env::args().collect();
   argument_count = argument_list.len();
@@ -73,20 +92,50 @@ async fn main() {
   argument_name = match argument_count {
     0 => argument_list[1].clone(),
     2 => argument_list[1].clone(),
+    3 if ["--from-file", "--threatlist-update", "--forget-site", "--explain", "--convert-config"].contains(&argument_list[1].as_str()) => argument_list[1].clone(),
+    3 | 4 if argument_list[1] == "--test-rules" => argument_list[1].clone(),
+    _ => String::new()
+  };
+  argument_value = match argument_count {
+    3 | 4 => argument_list[2].clone(),
+    _ => String::new()
+  };
+  argument_config_file = match argument_count {
+    4 => argument_list[3].clone(),
     _ => String::new()
   };
   error_code = -1;
   valid_url = false;
+  supported_schemes = config::get_supported_schemes();
 
   #[cfg(target_os = "windows")]
   unsafe {
     AttachConsole(ATTACH_PARENT_PROCESS);
   }
 
-  while let Some(u) = argument_list.pop() {
-    if webclient::validate_url(&u) || Path::new(&u).is_file() && !u.contains(&argument_appname) {
-      url_list = format!("{0},{1}", &u, url_list);
-      valid_url = true;
+  if argument_name == "-" || argument_name == "--from-file" {
+    // Read the URLs from stdin or a file, one URL per line
+    match read_url_list(argument_list.last().unwrap()) {
+      Ok(lines) => {
+        for u in lines {
+          if webclient::validate_url(&u, &supported_schemes) || localfile::LocalFile::new(&u).is_some() {
+            url_list.push(u);
+            valid_url = true;
+          } else {
+            println!("Ignoring invalid URL: '{}'", u);
+          }
+        }
+      },
+      Err(e) => {
+        println!("ERROR: Unable to read the URL list: {}", e);
+      }
+    }
+  } else {
+    while let Some(u) = argument_list.pop() {
+      if webclient::validate_url(&u, &supported_schemes) || localfile::LocalFile::new(&u).is_some() && !u.contains(&argument_appname) {
+        url_list.insert(0, u);
+        valid_url = true;
+      }
     }
   }
 
@@ -106,9 +155,84 @@ async fn main() {
       } else if argument_name == "--status" {
         setup::list_default_applications();
         error_code = 0;
+      } else if argument_name == "--threatlist-update" {
+        match threatlist::import_threat_list(Path::new(&argument_value)) {
+          Ok(count) => {
+            println!("Imported {} hash prefixes to '{}'", count, threatlist::get_threat_list_file().display());
+            error_code = 0;
+          },
+          Err(e) => {
+            println!("ERROR: Unable to import the threat list: {}", e);
+            error_code = 1;
+          }
+        }
+      } else if argument_name == "--remembered-sites" {
+        let remembered_sites:Vec<config::Rule> = config::get_remembered_sites();
+        if remembered_sites.len() == 0 {
+          println!("There are no remembered sites");
+        }
+        for rule in remembered_sites {
+          println!("{} -> {}", config::get_remembered_host(&rule).unwrap(), rule.browser);
+        }
+        error_code = 0;
+      } else if argument_name == "--forget-site" {
+        if config::forget_site(&argument_value) {
+          println!("Removed the remembered choice for '{}'", argument_value);
+          error_code = 0;
+        } else {
+          println!("ERROR: '{}' isn't a remembered site, use --remembered-sites to list them", argument_value);
+          error_code = 1;
+        }
+      } else if argument_name == "--explain" && argument_value == "" {
+        println!("ERROR: --explain requires a URL");
+        error_code = 1;
+      } else if argument_name == "--explain" {
+        match config::get_configuration() {
+          Ok(configuration) => {
+            explain::explain_url(&argument_value, &configuration, &supported_schemes, &sourceapp::detect_source_app());
+            error_code = 0;
+          },
+          Err(e) => {
+            println!("ERROR: {}", e);
+            exit(1);
+          }
+        }
+      } else if argument_name == "--test-rules" {
+        // Test a configuration file other than the user configuration, e.g. in CI
+        let loaded_configuration:Result<config::Configuration, String> = match argument_config_file.as_str() {
+          "" => config::get_configuration(),
+          config_file => config::load_configuration_file(&PathBuf::from(config_file))
+        };
+        let configuration:config::Configuration = match loaded_configuration {
+          Ok(configuration) => configuration,
+          Err(e) => {
+            println!("ERROR: {}", e);
+            exit(1);
+          }
+        };
+        if !ruletest::test_rules(Path::new(&argument_value), &configuration) {
+          exit(1);
+        }
+        error_code = 0;
+      } else if argument_name == "--convert-config" {
+        match config::convert_configuration(&argument_value) {
+          Ok(config_file) => {
+            println!("Converted the configuration to '{}'", config_file.display());
+            error_code = 0;
+          },
+          Err(e) => {
+            println!("ERROR: Unable to convert the configuration: {}", e);
+            error_code = 1;
+          }
+        }
+      } else if argument_name == "--clipboard" {
+        read_clipboard = true;
+        error_code = -1;
+      } else if (argument_name == "-" || argument_name == "--from-file") && !valid_url {
+        println!("ERROR: No valid URLs found in '{}'", argument_list.last().unwrap());
+        error_code = 1;
       } else if valid_url {
-        url_list = url_list.trim_end_matches(",").to_string();
-        // println!("{}:{} url_list: {}", file!(), line!(), url_list);
+        // println!("{}:{} url_list: {:?}", file!(), line!(), url_list);
         URL.with( |v| { *v.borrow_mut() = url_list });
         error_code = -1;
       } else {
@@ -122,66 +246,53 @@ async fn main() {
 
   match error_code {
     -1 => {
-      let charset_policy:Option<config::CharsetPolicy>;
-      let mut url_list:String = String::new();
-      let mut valid_urls:Vec<String> = vec![];
+      let mut url_list:Vec<String> = vec![];
+      let valid_urls:Vec<String>;
+      let source_app:Option<sourceapp::SourceApp>;
 
       // Read configuration and store settings in 'thread_local'
-      configuration = config::get_configuration();
-      if !valid_url { URL.with(|v| { *v.borrow_mut() = configuration.settings.homepage.clone(); }); }
-      ICON_SPACING.with(|v| { *v.borrow_mut() = configuration.settings.buttons.spacing.clone(); });
-
-      URL.with(|v| {url_list = v.borrow().to_string();});
-
-      charset_policy = configuration.settings.charset_policy;
-      url_list.split(",").for_each( |u| {
-        // Exit if the URL has 'invalid' characters
-        match charset_policy {
-          Some(x) => {
-            // println!("{}:{} url: {}", file!(), line!(), u);
-            if
-              check_url(&u, x.utf16, config::CharsetList::Utf16) == config::CharsetPolicyAction::Block ||
-              check_url(&u, x.utf32, config::CharsetList::Utf32) == config::CharsetPolicyAction::Block
-            {
-              if gtk::init().is_err() {
-                println!("Failed to initialize GTK.");
-                exit(1);
-              };
-              let dialog:MessageDialog = MessageDialog::builder()
-                .buttons(ButtonsType::Ok)
-                .message_type(MessageType::Error)
-                .title("Invalid URL")
-                .text("URL is blocked due\nto invalid characters")
-                .build();
-              dialog.run();
-              dialog.emit_close();
-              gtk::main_iteration();
-            } else if
-              check_url(&u, x.utf16, config::CharsetList::Utf16) == config::CharsetPolicyAction::Warn ||
-              check_url(&u, x.utf32, config::CharsetList::Utf32) == config::CharsetPolicyAction::Warn
-            {
-              if gtk::init().is_err() {
-                println!("Failed to initialize GTK.");
-                exit(1);
-              }
-              if show_dialog(&u) {
-                valid_urls.push(u.to_string());
-              }
-            } else {
-              // println!("{}:{} Saving url: {}", file!(), line!(), u);
-              valid_urls.push(u.to_string());
-            }
+      configuration = match config::get_configuration() {
+        Ok(configuration) => configuration,
+        Err(e) => {
+          show_error_dialog("Invalid configuration", &e);
+          exit(1);
+        }
+      };
+      if read_clipboard {
+        init_gtk();
+        match get_clipboard_url(&supported_schemes) {
+          Some(u) => {
+            URL.with(|v| { *v.borrow_mut() = vec![u]; });
+            valid_url = true;
           },
-          None => { }
+          None => { println!("No URL found in the clipboard"); }
         }
-      });
+      }
+      if !valid_url {
+        URL.with(|v| { *v.borrow_mut() = vec![configuration.settings.homepage.clone()]; });
+        NO_URL_ARGUMENT.with(|v| { *v.borrow_mut() = true; });
+      }
+      ICON_SPACING.with(|v| { *v.borrow_mut() = configuration.settings.buttons.spacing.clone(); });
+      RULES.with(|v| { *v.borrow_mut() = configuration.rule_set.rules.iter().map( |r| r.rule.clone() ).collect(); });
+
+      // Application that opened the URL, used by the routing rules
+      source_app = sourceapp::detect_source_app();
+      SOURCE_APP.with(|v| { *v.borrow_mut() = source_app.clone(); });
+
+      URL.with(|v| {url_list = v.borrow().clone();});
+
+      url_list = open_local_files(&url_list, &configuration);
+      url_list = prepare_urls(&url_list, &configuration, &supported_schemes);
+      url_list = check_charset_policy(&url_list, configuration.settings.charset_policy);
+      url_list = check_denylist(&url_list, &configuration);
+      valid_urls = check_threat_list(&url_list, configuration.settings.threat_policy);
 
       let mut user_launch_urls:Vec<String> = vec![];
       // Open the URL with the pre-defined browser
       valid_urls.iter().for_each( |u| {
         // println!("{}:{} Autolaunch url: {}", file!(), line!(), u);
-        match config::auto_launch_browser(u.to_string(), configuration.browsers_list.clone()) {
-          Some(browser) => { start_browser(browser, u, None); },
+        match config::auto_launch_browser(u, &configuration, &source_app) {
+          Some((rule, browser)) => { start_browser(browser, u, None, &configuration.browsers_list, Some(&rule)); },
           None => { user_launch_urls.push(u.to_string()); }
         }
       });
@@ -190,7 +301,8 @@ async fn main() {
         exit(0);
       }
 
-      URL.with( |v| { *v.borrow_mut() = user_launch_urls.join(",") });
+      STRIPPED_URLS.with( |v| { v.borrow_mut().retain( |s| user_launch_urls.contains(&s.url) ) });
+      URL.with( |v| { *v.borrow_mut() = user_launch_urls });
 
       // Check for upates
       std::thread::spawn( move || {
@@ -242,6 +354,193 @@ async fn main() {
   }
 }
 
+// Convert local files to 'file://' URLs. Files that browsers can't display are opened
+// with the handler configured for their MIME type, or rejected.
+fn open_local_files(url_list:&Vec<String>, configuration:&config::Configuration) -> Vec<String> {
+  let mut browser_urls:Vec<String> = vec![];
+
+  for u in url_list {
+    match localfile::LocalFile::new(u) {
+      Some(local_file) => {
+        if local_file.is_browser_file() {
+          browser_urls.push(local_file.url);
+        } else {
+          match local_file.get_handler(&configuration.settings.file_handlers) {
+            Some(handler) => { start_file_handler(&handler, &local_file); },
+            None => {
+              show_error_dialog("Unsupported file", &format!(
+                "'{}' is a '{}' file, which can't be opened with a browser.\nAdd a handler for this file type to 'file_handlers' in the configuration file.",
+                local_file.path.display(),
+                local_file.mime_type
+              ));
+            }
+          }
+        }
+      },
+      None => { browser_urls.push(u.to_string()); }
+    }
+  }
+
+  return browser_urls;
+}
+
+// Apply the URL transformations, in order, before checking the charset policy and auto launch rules
+fn prepare_urls(url_list:&Vec<String>, configuration:&config::Configuration, supported_schemes:&Vec<String>) -> Vec<String> {
+  let mut url_list:Vec<String> = url_list.clone();
+
+  // Replace links wrapped by redirectors with their destination, so that the charset
+  // policy, auto launch and host information use the real URL
+  url_list = url_list.iter()
+    .map( |u| webclient::unwrap_url(u, &configuration.settings.redirect_policy, supported_schemes) )
+    .collect();
+
+  // Find the destination of shortened links
+  url_list = url_list.iter()
+    .map( |u| webclient::expand_url(u, &configuration.settings.shortlink_policy, supported_schemes) )
+    .collect();
+
+  // Apply the user rewrite rules, e.g. to open a site in an alternative frontend
+  url_list = url_list.iter()
+    .map( |u| {
+      if webclient::validate_url(u, supported_schemes) {
+        return webclient::rewrite_url(u, &configuration.rewrite_rules, supported_schemes);
+      }
+      return u.to_string();
+    })
+    .collect();
+
+  // Remove tracking parameters, keeping the original URLs so they can be restored
+  url_list = url_list.iter()
+    .map( |u| {
+      match webclient::strip_tracking_parameters(u, &configuration.settings.tracking_policy) {
+        Some(stripped_url) => {
+          let clean_url:String = stripped_url.url.clone();
+          STRIPPED_URLS.with(|v| { v.borrow_mut().push(stripped_url); });
+          return clean_url;
+        },
+        None => { return u.to_string(); }
+      }
+    })
+    .collect();
+
+  return url_list;
+}
+
+// Returns the URLs allowed by the charset policy, displaying a warning or error message if required
+fn check_charset_policy(url_list:&Vec<String>, charset_policy:Option<config::CharsetPolicy>) -> Vec<String> {
+  let mut valid_urls:Vec<String> = vec![];
+
+  url_list.iter().for_each( |u| {
+    // Exit if the URL has 'invalid' characters or a lookalike host
+    match charset_policy {
+      Some(x) => {
+        // println!("{}:{} url: {}", file!(), line!(), u);
+        let analysis:charset::UrlAnalysis = charset::analyze_url(u, &x);
+        if analysis.action == config::CharsetPolicyAction::Block {
+          show_error_dialog("Invalid URL", &format!("URL is blocked due\nto invalid characters\n\n{}", analysis.get_report()));
+        } else if analysis.action == config::CharsetPolicyAction::Warn {
+          if show_dialog(u, &analysis) {
+            valid_urls.push(u.to_string());
+          }
+        } else {
+          // println!("{}:{} Saving url: {}", file!(), line!(), u);
+          valid_urls.push(u.to_string());
+        }
+      },
+      None => { }
+    }
+  });
+
+  return valid_urls;
+}
+
+// Returns the URLs allowed by the denylist, blocked domains are removed and the
+// user is asked to confirm the domains that require confirmation
+fn check_denylist(url_list:&Vec<String>, configuration:&config::Configuration) -> Vec<String> {
+  let mut valid_urls:Vec<String> = vec![];
+
+  url_list.iter().for_each( |u| {
+    match config::get_denylist_rule(u, configuration) {
+      Some(rule) => {
+        if check_policy_action(rule.action, u, "denylist", "Denylisted URL", &format!("Rule: {}", rule.name)) {
+          valid_urls.push(u.to_string());
+        }
+      },
+      None => { valid_urls.push(u.to_string()); }
+    }
+  });
+
+  return valid_urls;
+}
+
+// Returns the URLs not found in the offline threat list, displaying a warning or error message for the others
+fn check_threat_list(url_list:&Vec<String>, threat_policy:Option<config::ThreatPolicy>) -> Vec<String> {
+  let policy:config::ThreatPolicy;
+  let threat_list:threatlist::ThreatList;
+  let mut valid_urls:Vec<String> = vec![];
+
+  policy = match threat_policy {
+    Some(policy) if policy.check => policy,
+    _ => { return url_list.clone(); }
+  };
+  threat_list = match threatlist::ThreatList::load() {
+    Some(threat_list) => threat_list,
+    None => { return url_list.clone(); }
+  };
+
+  url_list.iter().for_each( |u| {
+    match threat_list.lookup(u) {
+      Some(expression) => {
+        if check_policy_action(policy.action, u, "threat list", "Unsafe URL", &format!("Match: {}", expression)) {
+          valid_urls.push(u.to_string());
+        }
+      },
+      None => { valid_urls.push(u.to_string()); }
+    }
+  });
+
+  return valid_urls;
+}
+
+// Returns true if a URL matched by the denylist or the threat list can be opened. Blocked URLs
+// display an error message, and the user is asked to confirm the URLs that require a warning.
+fn check_policy_action(action:config::CharsetPolicyAction, url:&str, policy:&str, title:&str, details:&str) -> bool {
+  match action {
+    config::CharsetPolicyAction::Block => {
+      show_error_dialog("Blocked URL", &format!("URL is blocked by the {}\n\n{}\n{}", policy, url, details));
+      return false;
+    },
+    config::CharsetPolicyAction::Warn => {
+      if show_warning_dialog(title, &format!("The URL '{}' is in the {}\nAre you sure that you want to proceed?", url, policy), details) {
+        return true;
+      }
+      println!("Aborting due to the {}, {}", policy, details);
+      return false;
+    },
+    config::CharsetPolicyAction::Allow => { return true; }
+  }
+}
+
+// Read newline separated URLs from a file, or from stdin if 'source' is '-'.
+// Empty lines and lines starting with '#' are ignored.
+fn read_url_list(source:&str) -> std::io::Result<Vec<String>> {
+  let mut data:String = String::new();
+
+  if source == "-" {
+    std::io::stdin().read_to_string(&mut data)?;
+  } else {
+    data = std::fs::read_to_string(source)?;
+  }
+
+  return Ok(
+    data.lines()
+      .map( |l| l.trim() )
+      .filter( |l| l.len() > 0 && !l.starts_with("#") )
+      .map( |l| l.to_string() )
+      .collect()
+  );
+}
+
 #[cfg(target_family = "windows")]
 fn send_return() {
   let mut input_u: INPUT_u = unsafe { std::mem::zeroed() };
@@ -289,6 +588,9 @@ fn show_application_window(configuration:config::Configuration) {
     let hostinfo_box:Box;
     let mut icons_row:Box = Box::new(Orientation::Horizontal, 0);
     let mut icon_counter:i32 = 1;
+    let mut button:Button;
+    let mut default_button:Option<(Button, String)> = None;
+    let default_browser:Option<config::BrowserSettings>;
     let icons_per_row:i32 = configuration.settings.buttons.per_row;
     let icon_spacing:i32 = configuration.settings.buttons.spacing;
     let icon_spacing_top:i32 = configuration.settings.buttons.spacing;
@@ -316,19 +618,55 @@ fn show_application_window(configuration:config::Configuration) {
 
     // Add all browsers as icons to a Box widget, creating a new child Box widget
     // for every 'icons_per_row' browsers
+    default_browser = match configuration.settings.window.default_browser {
+      Some(ref reference) => routing::get_browser(reference, &configuration.browsers_list),
+      None => None
+    };
     icons_box.add(&icons_row);
     for browser in configuration.browsers_list.clone() {
       if icon_counter % icons_per_row == 0 {
-        button_with_image(&app, &icons_row, &configuration.settings.buttons, &browser,  button_margin_last);
+        button = button_with_image(&app, &icons_row, &configuration.settings.buttons, &browser, &configuration.browsers_list, button_margin_last);
         icons_row = Box::new(Orientation::Horizontal, 0);
         icons_box.add(&icons_row);
       } else {
-        button_with_image(&app, &icons_row, &configuration.settings.buttons, &browser, button_margin_default);
+        button = button_with_image(&app, &icons_row, &configuration.settings.buttons, &browser, &configuration.browsers_list, button_margin_default);
+      }
+      match default_browser {
+        Some(ref default_browser) if default_browser.title == browser.title && default_button.is_none() => {
+          default_button = Some((button, browser.title.clone()));
+        },
+        _ => { }
       }
       icon_counter = icon_counter + 1;
     }
     window_box.add(&icons_box);
 
+    // Save the selected browser as a rule for the URL host
+    let remember_button:CheckButton = CheckButton::builder()
+      .label("_Remember my choice for this site")
+      .use_underline(true)
+      .can_focus(false)
+      .margin_start(icon_spacing)
+      .margin_top(icon_spacing)
+      .tooltip_text("Open links to this site with the selected browser, without displaying this window")
+      .build();
+    remember_button.connect_toggled( |btn| {
+      REMEMBER_CHOICE.with(|v| { *v.borrow_mut() = btn.is_active(); });
+    });
+    window_box.add(&remember_button);
+
+    // Routing rules skipped because of invalid patterns
+    if configuration.rule_set.errors.len() > 0 {
+      let report_label:Label = Label::builder()
+        .label(format!("\u{26A0} {} rule(s) with invalid patterns", configuration.rule_set.errors.len()))
+        .tooltip_text(configuration.rule_set.get_report())
+        .halign(Align::Start)
+        .margin_start(icon_spacing)
+        .margin_top(icon_spacing)
+        .build();
+      window_box.add(&report_label);
+    }
+
     // Check if we need to add taget URL host information
     if configuration.settings.host_info {
       hostinfo_box = diplay_host_info(button_width * icons_per_row + icon_spacing * icons_per_row - icon_spacing);
@@ -389,13 +727,22 @@ fn show_application_window(configuration:config::Configuration) {
     window.add(&window_box);
     window.show_all();
 
+    // Open the default browser when the countdown ends, unless the user interacts with the window
+    match (configuration.settings.window.timeout_seconds, default_button.clone()) {
+      (Some(timeout_seconds), Some((button, title))) if timeout_seconds > 0 => {
+        start_countdown(&window, button, title, timeout_seconds, configuration.settings.buttons.show_label || !configuration.settings.buttons.show_image);
+      },
+      _ => { }
+    }
+
   });
 
   application.run();
 }
 
-fn button_with_image(application:&Application, box_object:&Box, button_properties:&config::ButtonProperties, browser_settings:&config::BrowserSettings, margins:ButtonMargins) {
+fn button_with_image(application:&Application, box_object:&Box, button_properties:&config::ButtonProperties, browser_settings:&config::BrowserSettings, browsers_list:&Vec<config::BrowserSettings>, margins:ButtonMargins) -> Button {
   let browser_settings_clone:config::BrowserSettings;
+  let browsers_list_clone:Vec<config::BrowserSettings>;
   let application_clone:Application;
   let image:Image;
   let image_position:PositionType;
@@ -405,6 +752,7 @@ fn button_with_image(application:&Application, box_object:&Box, button_propertie
   // the closure in button connect_clicked
   application_clone = application.clone();
   browser_settings_clone = browser_settings.clone();
+  browsers_list_clone = browsers_list.clone();
   image_position = match button_properties.image_position.as_str() {
     "top" => PositionType::Top,
     "bottom" => PositionType::Bottom,
@@ -426,18 +774,103 @@ fn button_with_image(application:&Application, box_object:&Box, button_propertie
     button.set_label(&browser_settings.title);
     button.set_use_underline(true);
   }
-  button.connect_clicked(move |_| {button_clicked(&application_clone, &browser_settings_clone)});
+  button.set_tooltip_text(Some(&get_button_tooltip(browser_settings, browsers_list)));
+  button.connect_clicked(move |_| {button_clicked(&application_clone, &browser_settings_clone, &browsers_list_clone)});
 
   // Add to the main window
   box_object.add(&button);
+
+  return button;
 }
 
-fn button_clicked<'a>(application:&Application, browser_settings:&'a config::BrowserSettings ) {
-  let mut url_list:String = String::new();
-  URL.with(|v| {url_list = v.borrow().to_string();});
-  url_list.split(",").for_each( |u| {
+// The browser command, followed by the rules that add arguments or environment variables for the browser
+fn get_button_tooltip(browser_settings:&config::BrowserSettings, browsers_list:&Vec<config::BrowserSettings>) -> String {
+  let mut tooltip:Vec<String> = vec![format!("{} {}", browser_settings.executable, browser_settings.arguments).trim().to_string()];
+  let mut rules:Vec<config::Rule> = vec![];
+
+  RULES.with(|v| {rules = v.borrow().clone();});
+  for rule in rules {
+    let description:String = rule.get_launch_description();
+    let is_browser_rule:bool = match routing::get_browser(&rule.browser, browsers_list) {
+      Some(browser) => browser.title == browser_settings.title,
+      None => false
+    };
+    if is_browser_rule && description != "" {
+      tooltip.push(format!("Rule '{}': {}", rule.name, description));
+    }
+  }
+
+  return tooltip.join("\n");
+}
+
+// Display the remaining seconds on the default browser button, and click it when the countdown ends.
+// Any key press or mouse movement over the window cancels the countdown.
+fn start_countdown(window:&ApplicationWindow, button:Button, title:String, timeout_seconds:u32, show_label:bool) {
+  let cancelled:Rc<Cell<bool>> = Rc::new(Cell::new(false));
+  let remaining:Rc<Cell<u32>> = Rc::new(Cell::new(timeout_seconds));
+
+  let cancel = get_countdown_cancel(&cancelled, &button, &title, show_label);
+  window.add_events(gtk::gdk::EventMask::POINTER_MOTION_MASK | gtk::gdk::EventMask::KEY_PRESS_MASK);
+  let cancel_on_key = cancel.clone();
+  window.connect_key_press_event(move |_, _| {
+    cancel_on_key();
+    return gtk::glib::Propagation::Proceed;
+  });
+  window.connect_motion_notify_event(move |_, _| {
+    cancel();
+    return gtk::glib::Propagation::Proceed;
+  });
+
+  button.set_label(&format!("{} ({})", title, timeout_seconds));
+  button.set_use_underline(true);
+  gtk::glib::timeout_add_seconds_local(1, move || {
+    if cancelled.get() {
+      return gtk::glib::ControlFlow::Break;
+    }
+    remaining.set(remaining.get() - 1);
+    if remaining.get() == 0 {
+      cancelled.set(true);
+      button.clicked();
+      return gtk::glib::ControlFlow::Break;
+    }
+    button.set_label(&format!("{} ({})", title, remaining.get()));
+    return gtk::glib::ControlFlow::Continue;
+  });
+}
+
+// Returns a closure that stops the countdown and restores the button label
+fn get_countdown_cancel(cancelled:&Rc<Cell<bool>>, button:&Button, title:&str, show_label:bool) -> Rc<dyn Fn()> {
+  let cancelled:Rc<Cell<bool>> = cancelled.clone();
+  let button:Button = button.clone();
+  let title:String = title.to_string();
+
+  return Rc::new(move || {
+    if cancelled.get() {
+      return;
+    }
+    cancelled.set(true);
+    if show_label {
+      button.set_label(&title);
+    } else {
+      button.set_label("");
+    }
+  });
+}
+
+fn button_clicked<'a>(application:&Application, browser_settings:&'a config::BrowserSettings, browsers_list:&Vec<config::BrowserSettings>) {
+  let mut url_list:Vec<String> = vec![];
+  let mut remember_choice:bool = false;
+  let mut no_url_argument:bool = false;
+  URL.with(|v| {url_list = v.borrow().clone();});
+  REMEMBER_CHOICE.with(|v| {remember_choice = *v.borrow();});
+  NO_URL_ARGUMENT.with(|v| {no_url_argument = *v.borrow();});
+  url_list.iter().for_each( |u| {
     // println!("{}:{} button_clicked url: {}", file!(), line!(), &u);
-    start_browser(browser_settings.clone(), &u, Some(application));
+    // The homepage isn't a site selected by the user
+    if remember_choice && !no_url_argument {
+      config::remember_site(u, browser_settings);
+    }
+    start_browser(browser_settings.clone(), u, Some(application), browsers_list, None);
   });
 }
 
@@ -448,43 +881,41 @@ fn close_app<'a>(application:&'a Application) {
 fn diplay_host_info(max_width:i32) -> Box {
   let mut icon_spacing:i32 = 0;
   let download_icon_size:i32 = 100;
+  let small_button_size:i32 = 24;
   let box_object:Box;
   let button:Button;
+  let restore_button:Button;
+  let paste_button:Button;
   let pathbuf:PathBuf = config::get_resource_path("icons", "download.png");
   let image:Image = Image::from_file(pathbuf.clone());
   let label_url:Label;
-  let mut url:String = String::new();
-  let url_label:String;
-  let url_tooltip:String;
-  let url_list:Vec<&str>;
+  let mut label_width:i32;
+  let mut has_stripped_urls:bool = false;
+  let mut no_url_argument:bool = false;
 
   // Get variables stored in 'thread_local'
   ICON_SPACING.with(|v| {icon_spacing = *v.borrow();});
-  URL.with(|v| {url = v.borrow().to_string();});
+  STRIPPED_URLS.with(|v| {has_stripped_urls = v.borrow().len() > 0;});
+  NO_URL_ARGUMENT.with(|v| {no_url_argument = *v.borrow();});
 
-  url_list = url.split(",").collect();
-  match url_list.len() {
-    1 => {
-      url_label = format!("Url: {}", url);
-      url_tooltip = format!("Url: {}", url);
-    },
-    _ => {
-      url_label = format!("Open {} urls with", url_list.len());
-      url_tooltip = url_list.join("\n");
-    }
+  label_width = max_width - icon_spacing - download_icon_size;
+  if has_stripped_urls {
+    label_width = label_width - small_button_size - icon_spacing;
+  }
+  if no_url_argument {
+    label_width = label_width - small_button_size - icon_spacing;
   }
 
   // Create the Label objects
   label_url = Label::builder()
     .halign(Align::Start)
     .expand(false)
-    .width_request(max_width - icon_spacing - download_icon_size)
+    .width_request(label_width)
     .margin_start(download_icon_size / 2)
     .max_width_chars(30)
-    .label(&url_label)
-    .tooltip_text(&url_tooltip)
     .ellipsize(EllipsizeMode::End)
     .build();
+  update_url_label(&label_url);
 
   button = Button::builder()
     .halign(Align::End)
@@ -514,6 +945,87 @@ fn diplay_host_info(max_width:i32) -> Box {
     .build();
 
   box_object.add(&label_url);
+
+  // Allow the user to restore the tracking parameters removed from the URL,
+  // the button is only displayed if parameters were removed
+  restore_button = Button::builder()
+    .label("\u{21BA}")
+    .width_request(small_button_size)
+    .margin_start(icon_spacing)
+    .can_focus(false)
+    .no_show_all(true)
+    .build();
+  update_restore_button(&restore_button);
+
+  #[allow(deprecated)] // warning: use of deprecated macro `clone`: Using old-style clone! syntax
+  restore_button.connect_clicked(clone!(@strong label_url => move |btn| {
+    let mut url_list:Vec<String> = vec![];
+    let mut stripped_urls:Vec<webclient::StrippedUrl> = vec![];
+
+    URL.with(|v| {url_list = v.borrow().clone();});
+    STRIPPED_URLS.with(|v| {stripped_urls = v.borrow().clone();});
+    url_list = url_list.iter().map( |u| {
+      match stripped_urls.iter().find( |s| &s.url == u ) {
+        Some(stripped_url) => stripped_url.original.clone(),
+        None => u.to_string()
+      }
+    }).collect();
+    URL.with(|v| {*v.borrow_mut() = url_list});
+    STRIPPED_URLS.with(|v| {v.borrow_mut().clear()});
+
+    update_url_label(&label_url);
+    update_restore_button(btn);
+  }));
+  box_object.add(&restore_button);
+
+  // Load an URL from the clipboard when BrowseWith was started without URLs
+  if no_url_argument {
+    paste_button = Button::builder()
+      .image(&Image::from_icon_name(Some("edit-paste"), gtk::IconSize::Button))
+      .always_show_image(true)
+      .width_request(small_button_size)
+      .margin_start(icon_spacing)
+      .can_focus(false)
+      .tooltip_text("Open the URL in the clipboard")
+      .build();
+
+    #[allow(deprecated)] // warning: use of deprecated macro `clone`: Using old-style clone! syntax
+    paste_button.connect_clicked(clone!(@strong label_url, @strong restore_button => move |btn| {
+      let supported_schemes:Vec<String> = config::get_supported_schemes();
+      let configuration:config::Configuration;
+      let mut url_list:Vec<String>;
+
+      match get_clipboard_url(&supported_schemes) {
+        Some(u) => {
+          configuration = match config::get_configuration() {
+            Ok(configuration) => configuration,
+            Err(e) => {
+              show_error_dialog("Invalid configuration", &e);
+              return;
+            }
+          };
+          STRIPPED_URLS.with(|v| {v.borrow_mut().clear()});
+          url_list = prepare_urls(&vec![u], &configuration, &supported_schemes);
+          url_list = check_charset_policy(&url_list, configuration.settings.charset_policy);
+          url_list = check_denylist(&url_list, &configuration);
+          url_list = check_threat_list(&url_list, configuration.settings.threat_policy);
+          if url_list.len() > 0 {
+            URL.with(|v| {*v.borrow_mut() = url_list});
+            NO_URL_ARGUMENT.with(|v| {*v.borrow_mut() = false});
+          } else {
+            STRIPPED_URLS.with(|v| {v.borrow_mut().clear()});
+          }
+          update_url_label(&label_url);
+          update_restore_button(&restore_button);
+        },
+        None => {
+          btn.set_tooltip_text(Some("No URL found in the clipboard"));
+        }
+      }
+    }));
+    box_object.add(&paste_button);
+  }
+
   box_object.add(&button);
 
   button.connect_clicked(move |_| {
@@ -536,7 +1048,7 @@ fn diplay_host_info(max_width:i32) -> Box {
     match release_dialog.run() {
       gtk::ResponseType::Ok => {
         label_url.set_label(format!("Url: {}", git_release.html_url).as_str());
-        URL.with(|v| {*v.borrow_mut() = git_release.html_url});
+        URL.with(|v| {*v.borrow_mut() = vec![git_release.html_url]});
       },
       _ => { }
     };
@@ -580,6 +1092,86 @@ fn diplay_host_info(max_width:i32) -> Box {
   return box_object;
 }
 
+fn update_url_label(label:&Label) {
+  let mut url_list:Vec<String> = vec![];
+  let url_label:String;
+  let url_tooltip:String;
+
+  URL.with(|v| {url_list = v.borrow().clone();});
+  (url_label, url_tooltip) = get_url_label(&url_list);
+  label.set_label(&url_label);
+  label.set_tooltip_text(Some(&url_tooltip));
+}
+
+fn update_restore_button(button:&Button) {
+  let mut removed_parameters:Vec<String> = vec![];
+
+  STRIPPED_URLS.with(|v| {
+    v.borrow().iter().for_each( |s| { removed_parameters.extend(s.parameters.clone()); });
+  });
+
+  button.set_tooltip_text(Some(&format!("Removed tracking parameters:\n{}\nClick to restore them", removed_parameters.join(", "))));
+  button.set_visible(removed_parameters.len() > 0);
+}
+
+// Returns the first valid URL found in the clipboard or in the primary selection
+fn get_clipboard_url(supported_schemes:&Vec<String>) -> Option<String> {
+  let selections:[gtk::gdk::Atom; 2] = [gtk::gdk::SELECTION_CLIPBOARD, gtk::gdk::SELECTION_PRIMARY];
+
+  for selection in selections.iter() {
+    let clipboard:gtk::Clipboard = gtk::Clipboard::get(selection);
+    match clipboard.wait_for_text() {
+      Some(text) => {
+        for word in text.split_whitespace() {
+          let candidate:&str = word.trim_matches( |c| c == '<' || c == '>' || c == '"' || c == '\'' )
+            .trim_end_matches( |c| c == '.' || c == ',' || c == ';' );
+          if webclient::validate_url(candidate, supported_schemes) {
+            return Some(candidate.to_string());
+          }
+        }
+      },
+      None => { }
+    }
+  }
+
+  return None;
+}
+
+fn get_url_label(url_list:&Vec<String>) -> (String, String) {
+  let mut url_label:String;
+  let mut url_tooltip:String;
+
+  match url_list.len() {
+    1 => {
+      url_label = format!("Url: {}", url_list[0]);
+      url_tooltip = format!("Url: {}", url_list[0]);
+    },
+    _ => {
+      url_label = format!("Open {} urls with", url_list.len());
+      url_tooltip = url_list.join("\n");
+    }
+  }
+
+  STRIPPED_URLS.with(|v| {
+    if v.borrow().len() > 0 {
+      url_label = format!("{} (cleaned)", url_label);
+      url_tooltip = format!("{}\nTracking parameters were removed", url_tooltip);
+    }
+  });
+
+  SOURCE_APP.with(|v| {
+    match *v.borrow() {
+      Some(ref source_app) => {
+        url_label = format!("{} from {}", url_label, source_app.name);
+        url_tooltip = format!("{}\nOpened from: {}", url_tooltip, source_app.get_description());
+      },
+      None => { }
+    }
+  });
+
+  return (url_label, url_tooltip);
+}
+
 fn get_icon_image(file_path:&String) -> Image {
   let image:Image;
   let width_height:i32 = 24;
@@ -643,37 +1235,156 @@ fn get_icon_image(file_path:&String) -> Image {
   return image;
 }
 
-fn start_browser(browser_settings:config::BrowserSettings, url:&str, application:Option<&Application>) {
+// Start the browser, trying the browsers in its 'fallback' list, in order, if it fails to start.
+// The arguments and environment of the rule that selected the browser aren't used with the fallback browsers.
+fn start_browser(browser_settings:config::BrowserSettings, url:&str, application:Option<&Application>, browsers_list:&Vec<config::BrowserSettings>, rule:Option<&config::Rule>) {
+  let browsers:Vec<config::BrowserSettings> = routing::get_fallback_chain(&browser_settings, browsers_list);
+  let mut errors:Vec<String> = vec![];
+  let mut started:bool = false;
+
+  for (index, browser) in browsers.iter().enumerate() {
+    // Only wait for the browser to exit if there is another browser to try
+    match launch_browser(browser, url, index + 1 < browsers.len(), if index == 0 { rule } else { None }) {
+      Ok(..) => {
+        if errors.len() > 0 {
+          eprintln!("Opened '{}' with '{}'", url, browser.title);
+        }
+        started = true;
+        break;
+      },
+      Err(e) => {
+        eprintln!("Failed to start '{}': {}", browser.title, e);
+        errors.push(format!("{}: {}", browser.title, e));
+      }
+    }
+  }
+
+  if !started {
+    show_error_dialog("Unable to start the browser", &format!("Failed to open '{}'\n\n{}", url, errors.join("\n")));
+  }
+
+  match application {
+    Some(app) => {
+      close_app(&app);
+    },
+    None => { }
+  }
+}
+
+fn launch_browser(browser_settings:&config::BrowserSettings, url:&str, wait_for_exit:bool, rule:Option<&config::Rule>) -> Result<(), String> {
   let mut args:Vec<&str> = Vec::new();
+  let mut command:Command;
+  let mut child:std::process::Child;
+  let deadline:Instant;
 
+  command = Command::new(&browser_settings.executable);
   if browser_settings.arguments != "" {
     args.push(&browser_settings.arguments);
   }
-  url.split(",").for_each(|u| {
-    args.push(u);
-  });
+  // Rule arguments, e.g. a browser profile, are added after the browser arguments
+  match rule {
+    Some(rule) => {
+      match rule.arguments {
+        Some(ref arguments) => { args.extend(arguments.iter().map( |a| a.as_str() )); },
+        None => { }
+      }
+      match rule.environment {
+        Some(ref environment) => { command.envs(environment.iter()); },
+        None => { }
+      }
+    },
+    None => { }
+  }
+  // Pass the URL as received, it can contain commas
+  args.push(url);
 
-  Command::new(&browser_settings.executable)
+  child = command
     .args(args.iter())
     .stderr(Stdio::null())
     .stdout(Stdio::null())
     .spawn()
-    .expect("failed to execute process");
+    .map_err( |e| e.to_string() )?;
 
-  match application {
-    Some(app) => {
-      close_app(&app);
-    },
-    None => { }
+  if !wait_for_exit {
+    return Ok(());
+  }
+
+  // A browser that exits straight away with an error failed to start, e.g. a wrapper
+  // script left behind after the browser was removed
+  deadline = Instant::now() + Duration::from_millis(constants::BROWSER_START_GRACE_PERIOD);
+  loop {
+    match child.try_wait() {
+      Ok(Some(status)) if status.success() => { return Ok(()); },
+      Ok(Some(status)) => { return Err(format!("exited with {}", status)); },
+      Ok(None) => {
+        if Instant::now() >= deadline {
+          return Ok(());
+        }
+        std::thread::sleep(Duration::from_millis(50));
+      },
+      Err(e) => { return Err(e.to_string()); }
+    }
+  }
+}
+
+fn start_file_handler(handler:&config::FileHandler, local_file:&localfile::LocalFile) {
+  let mut args:Vec<&str> = Vec::new();
+
+  if handler.arguments != "" {
+    args.push(&handler.arguments);
+  }
+
+  match Command::new(&handler.executable).args(args.iter()).arg(&local_file.path).stderr(Stdio::null()).stdout(Stdio::null()).spawn() {
+    Ok(..) => { },
+    Err(e) => {
+      show_error_dialog("Unable to open file", &format!("Failed to start '{}' to open '{}'\n{}", handler.executable, local_file.path.display(), e));
+    }
   }
 }
 
-fn show_dialog(url:&str) -> bool {
+// Dialogs can be displayed before the main window, e.g. by the URL policies
+fn init_gtk() {
+  if gtk::init().is_err() {
+    println!("Failed to initialize GTK.");
+    exit(1);
+  };
+}
+
+fn show_error_dialog(title:&str, message:&str) {
+  println!("{}", message);
+  init_gtk();
+  let dialog:MessageDialog = MessageDialog::builder()
+    .buttons(ButtonsType::Ok)
+    .message_type(MessageType::Error)
+    .title(title)
+    .text(message)
+    .build();
+  dialog.run();
+  dialog.emit_close();
+  gtk::main_iteration();
+}
+
+fn show_dialog(url:&str, analysis:&charset::UrlAnalysis) -> bool {
+  let proceed:bool = show_warning_dialog(
+    "Invalid URL",
+    &format!("The URL '{}' might contain invalid characters\nAre you sure that you want to proceed?", url),
+    &analysis.get_report()
+  );
+
+  if !proceed {
+    println!("Aborting due to invalid characters in URL");
+  }
+  return proceed;
+}
+
+fn show_warning_dialog(title:&str, message:&str, details:&str) -> bool {
+  init_gtk();
   let message_dialog:MessageDialog = MessageDialog::builder()
     .buttons(ButtonsType::YesNo)
     .message_type(MessageType::Warning)
-    .title("Invalid URL")
-    .text(format!("The URL '{}' might contain invalid characters\nAre you sure that you want to proceed?", url))
+    .title(title)
+    .text(message)
+    .secondary_text(details)
     .build();
     // println!("{}:{} show_dialog: built", file!(), line!());
 
@@ -684,34 +1395,9 @@ fn show_dialog(url:&str) -> bool {
       return true;
     }
     _ => {
-      println!("Aborting due to invalid characters in URL");
       message_dialog.emit_close();
       gtk::main_iteration();
       return false;
     }
   }
 }
-
-fn check_url(url:&str, action:config::CharsetPolicyAction, charset:config::CharsetList) -> config::CharsetPolicyAction {
-  let mut test_url:String = url.to_string();
-  let mut detected:config::CharsetList = config::CharsetList::Unknown;
-
-  match test_url.pop() {
-    Some(c) => {
-      let len:usize = c.len_utf8();
-      if len == 1 { }
-      else if len == 2  && detected == config::CharsetList::Unknown {
-        detected = config::CharsetList::Utf16;
-      } else if len > 2 && detected == config::CharsetList::Unknown {
-        detected = config::CharsetList::Utf32;
-      }
-    },
-    None => { }
-  }
-
-  if detected == charset {
-    return action;
-  }
-
-  return config::CharsetPolicyAction::Allow;
-}
@@ -0,0 +1,76 @@
+use url::{ Url, Host };
+use std::net::{ IpAddr, Ipv4Addr, Ipv6Addr, ToSocketAddrs };
+use std::sync::mpsc;
+use std::time::{ Duration };
+
+use crate::config::{ IntranetPolicy };
+
+// Hosts are internal if they are a private, link-local or loopback address, or end with one
+// of the internal suffixes. Optionally the host name is resolved to check its addresses.
+pub fn is_intranet_url(url:&Url, policy:&IntranetPolicy) -> bool {
+  match url.host() {
+    Some(Host::Ipv4(address)) => { return is_private_ipv4(&address); },
+    Some(Host::Ipv6(address)) => { return is_private_ipv6(&address); },
+    Some(Host::Domain(domain)) => {
+      let domain:String = domain.trim_end_matches(".").to_lowercase();
+      if has_internal_suffix(&domain, &policy.suffixes) {
+        return true;
+      }
+      if policy.resolve {
+        return resolves_to_private_address(&domain, policy.timeout);
+      }
+      return false;
+    },
+    None => { return false; }
+  }
+}
+
+fn has_internal_suffix(domain:&str, suffixes:&Vec<String>) -> bool {
+  return suffixes.iter().any( |suffix| {
+    let suffix:String = suffix.trim_start_matches(".").to_lowercase();
+    suffix != "" && (domain == suffix || domain.ends_with(&format!(".{}", suffix)))
+  });
+}
+
+// 10.0.0.0/8, 172.16.0.0/12, 192.168.0.0/16, 169.254.0.0/16 and 127.0.0.0/8
+fn is_private_ipv4(address:&Ipv4Addr) -> bool {
+  return address.is_private() || address.is_link_local() || address.is_loopback();
+}
+
+// fc00::/7, fe80::/10 and ::1, IPv4 mapped addresses are checked as IPv4
+fn is_private_ipv6(address:&Ipv6Addr) -> bool {
+  let first_segment:u16 = address.segments()[0];
+
+  match address.to_ipv4_mapped() {
+    Some(ipv4_address) => { return is_private_ipv4(&ipv4_address); },
+    None => { }
+  }
+  return address.is_loopback() || (first_segment & 0xfe00) == 0xfc00 || (first_segment & 0xffc0) == 0xfe80;
+}
+
+// The lookup runs in another thread, so that a slow resolver doesn't delay opening the URL
+// for longer than the timeout. Names that don't resolve in time aren't internal.
+fn resolves_to_private_address(domain:&str, timeout:u64) -> bool {
+  let (sender, receiver) = mpsc::channel::<Vec<IpAddr>>();
+  let domain:String = domain.to_string();
+
+  std::thread::spawn( move || {
+    let addresses:Vec<IpAddr> = match (domain.as_str(), 0).to_socket_addrs() {
+      Ok(addresses) => addresses.map( |a| a.ip() ).collect(),
+      Err(..) => vec![]
+    };
+    let _ = sender.send(addresses);
+  });
+
+  match receiver.recv_timeout(Duration::from_millis(timeout)) {
+    Ok(addresses) => {
+      return addresses.len() > 0 && addresses.iter().all( |address| {
+        match address {
+          IpAddr::V4(address) => is_private_ipv4(address),
+          IpAddr::V6(address) => is_private_ipv6(address)
+        }
+      });
+    },
+    Err(..) => { return false; }
+  }
+}
@@ -0,0 +1,605 @@
+use url::{ Url };
+use regex::{ Regex, RegexBuilder };
+use std::cell::{ RefCell };
+
+use crate::config::{ AutoLaunch, BrowserSettings, Condition, Configuration, DenylistPatternType, DenylistRule, IntranetPolicy, Rule };
+use crate::sourceapp::{ SourceApp };
+
+mod intranet;
+
+// The URL being routed and the application that opened it
+pub struct Request<'a> {
+  pub url: Url,
+  pub text: &'a str,
+  pub source_app: &'a Option<SourceApp>,
+  // The intranet check can resolve the host name, so it is only done once per URL
+  intranet: RefCell<Option<bool>>
+}
+
+impl<'a> Request<'a> {
+  pub fn new(request_url:&'a str, source_app:&'a Option<SourceApp>) -> Option<Request<'a>> {
+    match Url::parse(request_url) {
+      Ok(url) => { return Some(Request { url: url, text: request_url, source_app: source_app, intranet: RefCell::new(None) }); },
+      Err(..) => { return None; }
+    }
+  }
+}
+
+// Returns the configured rules followed by the rules generated from the browsers 'auto_launch'
+// lists, sorted by priority. Rules with the same priority keep their order, so the
+// 'auto_launch' lists are still checked in the same order as the browsers.
+pub fn get_rules(configuration:&Configuration) -> Vec<Rule> {
+  let mut rules:Vec<Rule> = vec![];
+
+  match configuration.rules {
+    Some(ref configured_rules) => { rules.extend(configured_rules.iter().cloned()); },
+    None => { }
+  }
+  for browser in &configuration.browsers_list {
+    match browser.auto_launch {
+      Some(ref auto_launch_list) => {
+        for auto_launch in auto_launch_list {
+          match get_auto_launch_condition(auto_launch) {
+            Some((name, condition)) => {
+              let (arguments, environment) = match auto_launch {
+                AutoLaunch::Conditions { arguments, environment, .. } => (arguments.clone(), environment.clone()),
+                AutoLaunch::Pattern(..) => (None, None)
+              };
+              rules.push(Rule {
+                name: format!("auto_launch '{}'", name),
+                priority: 0,
+                browser: get_browser_reference(browser),
+                condition: condition,
+                remembered: None,
+                arguments: arguments,
+                environment: environment
+              });
+            },
+            None => { println!("Ignoring empty auto_launch entry for '{}'", browser.title); }
+          }
+        }
+      },
+      None => { }
+    }
+  }
+
+  // Higher priorities first
+  rules.sort_by( |a, b| b.priority.cmp(&a.priority) );
+
+  return rules;
+}
+
+// Returns the name and the condition of an 'auto_launch' entry
+fn get_auto_launch_condition(auto_launch:&AutoLaunch) -> Option<(String, Condition)> {
+  let mut names:Vec<String> = vec![];
+  let mut conditions:Vec<Condition> = vec![];
+
+  match auto_launch {
+    AutoLaunch::Pattern(pattern) => {
+      return Some((pattern.to_string(), Condition::Url(pattern.to_string())));
+    },
+    AutoLaunch::Conditions { url, source_app, .. } => {
+      match url {
+        Some(pattern) => {
+          names.push(pattern.to_string());
+          conditions.push(Condition::Url(pattern.to_string()));
+        },
+        None => { }
+      }
+      match source_app {
+        Some(pattern) => {
+          names.push(format!("from {}", pattern));
+          conditions.push(Condition::SourceApp(pattern.to_string()));
+        },
+        None => { }
+      }
+    }
+  }
+
+  if conditions.len() == 0 {
+    return None;
+  }
+  return Some((names.join(" "), Condition::All(conditions)));
+}
+
+// Conditions with the regular expressions already compiled
+#[derive(Clone)]
+enum Matcher {
+  Scheme(String),
+  Host(Regex),
+  Path(Regex),
+  QueryKey(String),
+  Port(u16),
+  Url(Regex),
+  SourceApp(Regex),
+  Intranet(bool, IntranetPolicy),
+  All(Vec<Matcher>),
+  Any(Vec<Matcher>),
+  Not(Box<Matcher>)
+}
+
+#[derive(Clone)]
+pub struct CompiledRule {
+  pub rule: Rule,
+  matcher: Matcher
+}
+
+#[derive(Clone)]
+pub struct PatternError {
+  pub browser: String,
+  pub rule: String,
+  pub pattern: String,
+  pub error: String
+}
+
+impl PatternError {
+  pub fn get_description(&self) -> String {
+    return format!("{}: rule {}, pattern '{}'\n  {}", self.browser, self.rule, self.pattern, self.error.replace("\n", "\n  "));
+  }
+}
+
+// Denylist patterns with the host already lowercased or the regular expression compiled
+#[derive(Clone)]
+enum HostMatcher {
+  Exact(String),
+  Subdomain(String),
+  Regex(Regex),
+  Any
+}
+
+#[derive(Clone)]
+pub struct CompiledDenylistRule {
+  pub rule: DenylistRule,
+  matcher: HostMatcher
+}
+
+// Rules compiled when the configuration is loaded, rules with invalid patterns are skipped.
+// Denylist rules with invalid patterns match every host instead, so a mistake in the
+// pattern doesn't let the hosts it should block through.
+#[derive(Clone, Default)]
+pub struct RuleSet {
+  pub rules: Vec<CompiledRule>,
+  pub denylist: Vec<CompiledDenylistRule>,
+  pub errors: Vec<PatternError>
+}
+
+impl RuleSet {
+  pub fn new(configuration:&Configuration) -> RuleSet {
+    let mut rule_set:RuleSet = RuleSet::default();
+
+    let intranet_policy:IntranetPolicy = configuration.settings.intranet.clone().unwrap_or_default();
+
+    for rule in get_rules(configuration) {
+      let mut errors:Vec<(String, String)> = vec![];
+      let matcher:Matcher = compile_condition(&rule.condition, &intranet_policy, &mut errors);
+
+      if errors.len() == 0 {
+        rule_set.rules.push(CompiledRule { rule: rule, matcher: matcher });
+        continue;
+      }
+      let browser:String = match get_browser(&rule.browser, &configuration.browsers_list) {
+        Some(browser) => browser.title,
+        None => rule.browser.to_string()
+      };
+      for (pattern, error) in errors {
+        rule_set.errors.push(PatternError { browser: browser.clone(), rule: rule.name.to_string(), pattern: pattern, error: error });
+      }
+    }
+
+    match configuration.settings.denylist {
+      Some(ref denylist) => {
+        for rule in denylist {
+          let matcher:HostMatcher = match compile_denylist_pattern(rule) {
+            Ok(matcher) => matcher,
+            Err(error) => {
+              rule_set.errors.push(PatternError {
+                browser: String::from("Denylist"),
+                rule: rule.name.to_string(),
+                pattern: rule.pattern.to_string(),
+                error: format!("{}\nThe rule matches every URL", error)
+              });
+              HostMatcher::Any
+            }
+          };
+          rule_set.denylist.push(CompiledDenylistRule { rule: rule.clone(), matcher: matcher });
+        }
+      },
+      None => { }
+    }
+
+    return rule_set;
+  }
+
+  pub fn get_report(&self) -> String {
+    let mut report:Vec<String> = vec![format!("{} rule(s) with invalid patterns, routing rules are ignored and denylist rules match every URL:", self.errors.len())];
+    for error in &self.errors {
+      report.push(error.get_description());
+    }
+    return report.join("\n");
+  }
+}
+
+fn compile_condition(condition:&Condition, intranet_policy:&IntranetPolicy, errors:&mut Vec<(String, String)>) -> Matcher {
+  match condition {
+    Condition::Scheme(scheme) => { return Matcher::Scheme(scheme.to_lowercase()); },
+    Condition::Host(pattern) => { return Matcher::Host(compile_pattern(pattern, &get_glob_expression(&pattern.to_lowercase()), errors)); },
+    Condition::Path(pattern) => { return Matcher::Path(compile_pattern(pattern, &get_glob_expression(pattern), errors)); },
+    Condition::QueryKey(key) => { return Matcher::QueryKey(key.to_string()); },
+    Condition::Port(port) => { return Matcher::Port(*port); },
+    Condition::Url(pattern) => { return Matcher::Url(compile_pattern(pattern, pattern, errors)); },
+    Condition::SourceApp(pattern) => { return Matcher::SourceApp(compile_pattern(pattern, &format!("(?i){}", pattern), errors)); },
+    Condition::Intranet(intranet) => { return Matcher::Intranet(*intranet, intranet_policy.clone()); },
+    Condition::All(conditions) => { return Matcher::All(conditions.iter().map( |c| compile_condition(c, intranet_policy, errors) ).collect()); },
+    Condition::Any(conditions) => { return Matcher::Any(conditions.iter().map( |c| compile_condition(c, intranet_policy, errors) ).collect()); },
+    Condition::Not(condition) => { return Matcher::Not(Box::new(compile_condition(condition, intranet_policy, errors))); }
+  }
+}
+
+// Returns a regular expression that never matches if the pattern is invalid, the rule is dropped anyway
+fn compile_pattern(pattern:&str, expression:&str, errors:&mut Vec<(String, String)>) -> Regex {
+  match Regex::new(expression) {
+    Ok(re) => { return re; },
+    Err(e) => {
+      errors.push((pattern.to_string(), e.to_string()));
+      return Regex::new("$^").unwrap();
+    }
+  }
+}
+
+fn compile_denylist_pattern(rule:&DenylistRule) -> Result<HostMatcher, String> {
+  match rule.pattern_type {
+    DenylistPatternType::Exact => { return Ok(HostMatcher::Exact(rule.pattern.trim_end_matches(".").to_lowercase())); },
+    // '*.example.com' matches all the subdomains of 'example.com'
+    DenylistPatternType::Wildcard => {
+      if !rule.pattern.starts_with("*.") || rule.pattern.len() < 3 {
+        return Err(String::from("Wildcard patterns must start with '*.'"));
+      }
+      return Ok(HostMatcher::Subdomain(rule.pattern[1..].trim_end_matches(".").to_lowercase()));
+    },
+    DenylistPatternType::Regex => {
+      match RegexBuilder::new(&rule.pattern).case_insensitive(true).build() {
+        Ok(re) => { return Ok(HostMatcher::Regex(re)); },
+        Err(e) => { return Err(e.to_string()); }
+      }
+    }
+  }
+}
+
+// Returns the first denylist rule matching the URL host
+pub fn get_denylist_rule(request_url:&str, rule_set:&RuleSet) -> Option<DenylistRule> {
+  let host:String;
+
+  host = match Url::parse(request_url) {
+    Ok(url) => url.host_str().unwrap_or("").trim_end_matches(".").to_lowercase(),
+    Err(..) => { return None; }
+  };
+  if host == "" {
+    return None;
+  }
+
+  for compiled_rule in &rule_set.denylist {
+    let is_match:bool = match compiled_rule.matcher {
+      HostMatcher::Exact(ref pattern) => host == *pattern,
+      HostMatcher::Subdomain(ref domain) => host.ends_with(domain.as_str()),
+      HostMatcher::Regex(ref re) => re.is_match(&host),
+      HostMatcher::Any => true
+    };
+    if is_match {
+      return Some(compiled_rule.rule.clone());
+    }
+  }
+
+  return None;
+}
+
+// Returns the first rule matching the URL and the browser it refers to
+pub fn get_matching_rule(request_url:&str, configuration:&Configuration, source_app:&Option<SourceApp>) -> Option<(Rule, BrowserSettings)> {
+  let request:Request;
+
+  request = match Request::new(request_url, source_app) {
+    Some(request) => request,
+    None => { return None; }
+  };
+
+  for compiled_rule in &configuration.rule_set.rules {
+    if !is_match(&compiled_rule.matcher, &request) {
+      continue;
+    }
+    match get_browser(&compiled_rule.rule.browser, &configuration.browsers_list) {
+      Some(browser) => { return Some((compiled_rule.rule.clone(), browser)); },
+      None => { println!("Rule '{}' refers to an unknown browser: '{}'", compiled_rule.rule.name, compiled_rule.rule.browser); }
+    }
+  }
+
+  return None;
+}
+
+// Evaluates all the rules in order and returns if each one matched, used by '--explain'
+pub fn get_rule_trace(request_url:&str, configuration:&Configuration, source_app:&Option<SourceApp>) -> Vec<(Rule, bool)> {
+  let request:Request;
+
+  request = match Request::new(request_url, source_app) {
+    Some(request) => request,
+    None => { return vec![]; }
+  };
+
+  return configuration.rule_set.rules.iter()
+    .map( |compiled_rule| (compiled_rule.rule.clone(), is_match(&compiled_rule.matcher, &request)) )
+    .collect();
+}
+
+// Browsers are referenced by 'id' or by 'title', the title can be used with or without
+// the underscore that sets the button hotkey
+pub fn get_browser(reference:&str, browsers_list:&Vec<BrowserSettings>) -> Option<BrowserSettings> {
+  for browser in browsers_list {
+    match browser.id {
+      Some(ref id) if id == reference => { return Some(browser.clone()); },
+      _ => { }
+    }
+  }
+  for browser in browsers_list {
+    if browser.title == reference || browser.title.replace("_", "") == reference {
+      return Some(browser.clone());
+    }
+  }
+
+  return None;
+}
+
+// Returns the browser followed by the browsers in its 'fallback' list
+pub fn get_fallback_chain(browser:&BrowserSettings, browsers_list:&Vec<BrowserSettings>) -> Vec<BrowserSettings> {
+  let mut browsers:Vec<BrowserSettings> = vec![browser.clone()];
+
+  match browser.fallback {
+    Some(ref fallback_list) => {
+      for reference in fallback_list {
+        match get_browser(reference, browsers_list) {
+          Some(fallback) => {
+            if !browsers.iter().any( |b| b.title == fallback.title ) {
+              browsers.push(fallback);
+            }
+          },
+          None => { println!("Unknown fallback browser for '{}': '{}'", browser.title, reference); }
+        }
+      }
+    },
+    None => { }
+  }
+
+  return browsers;
+}
+
+pub fn get_browser_reference(browser:&BrowserSettings) -> String {
+  match browser.id {
+    Some(ref id) => { return id.to_string(); },
+    None => { return browser.title.to_string(); }
+  }
+}
+
+fn is_match(matcher:&Matcher, request:&Request) -> bool {
+  let url:&Url = &request.url;
+
+  match matcher {
+    Matcher::Scheme(scheme) => {
+      return url.scheme() == scheme;
+    },
+    Matcher::Host(re) => {
+      return re.is_match(&url.host_str().unwrap_or("").trim_end_matches(".").to_lowercase());
+    },
+    Matcher::Path(re) => {
+      return re.is_match(url.path());
+    },
+    Matcher::QueryKey(key) => {
+      return url.query_pairs().any( |(k, _)| k == key.as_str() );
+    },
+    Matcher::Port(port) => {
+      return url.port_or_known_default() == Some(*port);
+    },
+    Matcher::Url(re) => {
+      return re.is_match(request.text);
+    },
+    // Never matches if the application couldn't be detected
+    Matcher::SourceApp(re) => {
+      match request.source_app {
+        Some(source_app) => { return source_app.is_match(re); },
+        None => { return false; }
+      }
+    },
+    Matcher::Intranet(intranet, policy) => {
+      let mut is_intranet:Option<bool> = *request.intranet.borrow();
+      if is_intranet.is_none() {
+        is_intranet = Some(intranet::is_intranet_url(url, policy));
+        *request.intranet.borrow_mut() = is_intranet;
+      }
+      return is_intranet == Some(*intranet);
+    },
+    Matcher::All(matchers) => {
+      return matchers.iter().all( |m| is_match(m, request) );
+    },
+    Matcher::Any(matchers) => {
+      return matchers.iter().any( |m| is_match(m, request) );
+    },
+    Matcher::Not(matcher) => {
+      return !is_match(matcher, request);
+    }
+  }
+}
+
+// '*' matches any number of characters and '?' matches a single character
+fn get_glob_expression(pattern:&str) -> String {
+  let mut expression:String = String::from("^");
+
+  for c in pattern.chars() {
+    match c {
+      '*' => { expression.push_str(".*"); },
+      '?' => { expression.push_str("."); },
+      _ => { expression.push_str(&regex::escape(&c.to_string())); }
+    }
+  }
+  expression.push_str("$");
+
+  return expression;
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use serde_json::{ json, Value };
+
+  fn get_configuration(browsers_list:Value, rules:Value, denylist:Value) -> Configuration {
+    let mut document:Value = serde_json::from_str(include_str!("../../resources/config.json")).unwrap();
+    let mut configuration:Configuration;
+
+    document["browsers_list"] = browsers_list;
+    document["rules"] = rules;
+    document["settings"]["denylist"] = denylist;
+    configuration = serde_json::from_value(document).unwrap();
+    configuration.rule_set = RuleSet::new(&configuration);
+    return configuration;
+  }
+
+  fn get_browser_json(title:&str, auto_launch:Value) -> Value {
+    return json!({
+      "title": title,
+      "id": title.to_lowercase(),
+      "executable": format!("/usr/bin/{}", title.to_lowercase()),
+      "arguments": "%s",
+      "icon": "",
+      "auto_launch": auto_launch
+    });
+  }
+
+  fn get_browser_title(url:&str, configuration:&Configuration, source_app:&Option<SourceApp>) -> Option<String> {
+    return get_matching_rule(url, configuration, source_app).map( |(_, browser)| browser.title );
+  }
+
+  fn get_denylist_name(url:&str, configuration:&Configuration) -> Option<String> {
+    return get_denylist_rule(url, &configuration.rule_set).map( |rule| rule.name );
+  }
+
+  #[test]
+  fn matches_denylist_hosts() {
+    let configuration:Configuration = get_configuration(json!([]), json!([]), json!([
+      { "name": "Exact", "pattern": "Tracker.Example.com", "pattern_type": "Exact", "action": "Block" },
+      { "name": "Wildcard", "pattern": "*.doubleclick.net", "pattern_type": "Wildcard", "action": "Block" },
+      { "name": "Regex", "pattern": "^MAIL\\.(google|yahoo)\\.com$", "pattern_type": "Regex", "action": "Warn" }
+    ]));
+
+    assert_eq!(configuration.rule_set.errors.len(), 0);
+    assert_eq!(get_denylist_name("https://tracker.example.com./", &configuration), Some(String::from("Exact")));
+    assert_eq!(get_denylist_name("https://ad.DoubleClick.net/x", &configuration), Some(String::from("Wildcard")));
+    assert_eq!(get_denylist_name("https://doubleclick.net/", &configuration), None);
+    assert_eq!(get_denylist_name("https://notdoubleclick.net/", &configuration), None);
+    assert_eq!(get_denylist_name("https://mail.google.com/", &configuration), Some(String::from("Regex")));
+    assert_eq!(get_denylist_name("https://example.com/", &configuration), None);
+  }
+
+  #[test]
+  fn invalid_denylist_patterns_match_every_url() {
+    let configuration:Configuration = get_configuration(json!([]), json!([]), json!([
+      { "name": "Regex", "pattern": "(bad", "pattern_type": "Regex", "action": "Block" },
+      { "name": "Wildcard", "pattern": "doubleclick.net", "pattern_type": "Wildcard", "action": "Warn" }
+    ]));
+
+    assert_eq!(configuration.rule_set.errors.len(), 2);
+    assert_eq!(configuration.rule_set.errors[0].browser, "Denylist");
+    assert_eq!(configuration.rule_set.errors[1].pattern, "doubleclick.net");
+    assert_eq!(get_denylist_name("https://example.com/", &configuration), Some(String::from("Regex")));
+  }
+
+  #[test]
+  fn orders_rules_by_priority() {
+    let configuration:Configuration = get_configuration(
+      json!([ get_browser_json("Firefox", json!([ "mozilla" ])), get_browser_json("Chromium", json!([ "chromium" ])) ]),
+      json!([
+        { "name": "Low", "priority": -1, "browser": "firefox", "condition": { "scheme": "https" } },
+        { "name": "First", "priority": 10, "browser": "firefox", "condition": { "scheme": "https" } },
+        { "name": "Default", "priority": 0, "browser": "chromium", "condition": { "scheme": "ftp" } },
+        { "name": "Second", "priority": 10, "browser": "chromium", "condition": { "scheme": "https" } },
+        { "name": "Middle", "priority": 5, "browser": "chromium", "condition": { "scheme": "https" } }
+      ]),
+      json!([])
+    );
+    let names:Vec<String> = get_rules(&configuration).iter().map( |rule| rule.name.to_string() ).collect();
+
+    // Rules with the same priority keep their order, the 'auto_launch' rules come after the configured ones
+    assert_eq!(names, vec!["First", "Second", "Middle", "Default", "auto_launch 'mozilla'", "auto_launch 'chromium'", "Low"]);
+    assert_eq!(get_browser_title("https://example.com/", &configuration, &None), Some(String::from("Firefox")));
+  }
+
+  #[test]
+  fn converts_auto_launch_entries() {
+    let configuration:Configuration = get_configuration(
+      json!([
+        get_browser_json("Firefox", json!([ "^https://mozilla\\.org/", { "url": "^https://docs\\.", "source_app": "terminal", "arguments": [ "--new-tab" ] } ])),
+        get_browser_json("Chromium", json!(null))
+      ]),
+      json!(null),
+      json!(null)
+    );
+    let rules:Vec<Rule> = get_rules(&configuration);
+
+    assert_eq!(rules.len(), 2);
+    assert_eq!(rules[0].name, "auto_launch '^https://mozilla\\.org/'");
+    assert_eq!(rules[0].priority, 0);
+    assert_eq!(rules[0].browser, "firefox");
+    assert_eq!(rules[1].name, "auto_launch '^https://docs\\. from terminal'");
+    assert_eq!(rules[1].arguments, Some(vec![String::from("--new-tab")]));
+  }
+
+  #[test]
+  fn auto_launch_selects_the_first_browser_in_the_list() {
+    let configuration:Configuration = get_configuration(
+      json!([
+        get_browser_json("Firefox", json!([ "^https://www\\.mozilla\\.org/", "example\\.com" ])),
+        get_browser_json("Chromium", json!([ "example" ])),
+        get_browser_json("Epiphany", json!([ "gnome\\.org" ]))
+      ]),
+      json!(null),
+      json!(null)
+    );
+
+    assert_eq!(get_browser_title("https://example.com/", &configuration, &None), Some(String::from("Firefox")));
+    assert_eq!(get_browser_title("https://example.org/", &configuration, &None), Some(String::from("Chromium")));
+    assert_eq!(get_browser_title("https://www.gnome.org/", &configuration, &None), Some(String::from("Epiphany")));
+    assert_eq!(get_browser_title("https://rust-lang.org/", &configuration, &None), None);
+  }
+
+  #[test]
+  fn matches_conditions() {
+    let configuration:Configuration = get_configuration(
+      json!([ get_browser_json("Firefox", json!(null)), get_browser_json("Chromium", json!(null)) ]),
+      json!([
+        { "name": "Docs", "priority": 3, "browser": "firefox", "condition": { "all": [
+          { "host": "*.Example.com" }, { "path": "/docs/*.html" }, { "not": { "query_key": "print" } }
+        ] } },
+        { "name": "Admin", "priority": 2, "browser": "chromium", "condition": { "any": [ { "port": 8443 }, { "host": "admin.?.example.com" } ] } },
+        { "name": "Terminal", "priority": 1, "browser": "chromium", "condition": { "source_app": "TERMINAL" } }
+      ]),
+      json!([])
+    );
+    let source_app:Option<SourceApp> = Some(SourceApp { pid: 1, name: String::from("gnome-terminal"), executable: String::new(), command_line: String::new() });
+
+    assert_eq!(get_browser_title("https://www.EXAMPLE.com/docs/intro.html", &configuration, &None), Some(String::from("Firefox")));
+    assert_eq!(get_browser_title("https://www.example.com/docs/intro.html?print=1", &configuration, &None), None);
+    assert_eq!(get_browser_title("https://www.example.com/docs/intro.pdf", &configuration, &None), None);
+    assert_eq!(get_browser_title("https://intranet:8443/", &configuration, &None), Some(String::from("Chromium")));
+    assert_eq!(get_browser_title("https://admin.1.example.com/", &configuration, &None), Some(String::from("Chromium")));
+    assert_eq!(get_browser_title("https://admin.10.example.com/", &configuration, &None), None);
+    assert_eq!(get_browser_title("https://example.org/", &configuration, &source_app), Some(String::from("Chromium")));
+  }
+
+  #[test]
+  fn ignores_rules_with_invalid_patterns() {
+    let configuration:Configuration = get_configuration(
+      json!([ get_browser_json("Firefox", json!([ "(bad", "example" ])) ]),
+      json!(null),
+      json!(null)
+    );
+
+    assert_eq!(configuration.rule_set.rules.len(), 1);
+    assert_eq!(configuration.rule_set.errors.len(), 1);
+    assert_eq!(configuration.rule_set.errors[0].browser, "Firefox");
+    assert_eq!(get_browser_title("https://example.com/", &configuration, &None), Some(String::from("Firefox")));
+  }
+}
@@ -0,0 +1,101 @@
+use serde::{ Deserialize };
+use std::fs;
+use std::path::{ Path };
+
+use crate::config;
+use crate::config::{ BrowserSettings, Configuration };
+use crate::routing;
+use crate::sourceapp::{ SourceApp };
+
+// Expected browser for a URL, 'browser' is the id or title of the browser,
+// or null if the main window should be displayed
+#[derive(Deserialize)]
+pub struct TestCase {
+  pub url: String,
+  pub source_app: Option<String>,
+  pub browser: Option<String>
+}
+
+// Check the routing rules with the test cases, printing the cases that failed as a diff.
+// Returns false if any case failed or any rule was ignored because of an invalid pattern.
+// Nothing is launched and no window is displayed.
+pub fn test_rules(cases_file:&Path, configuration:&Configuration) -> bool {
+  let test_cases:Vec<TestCase>;
+  let mut failed:usize = 0;
+
+  test_cases = match fs::read_to_string(cases_file) {
+    Ok(contents) => {
+      match serde_json::from_str(&contents) {
+        Ok(test_cases) => test_cases,
+        Err(e) => {
+          println!("ERROR: Invalid test cases file '{}': {}", cases_file.display(), e);
+          return false;
+        }
+      }
+    },
+    Err(e) => {
+      println!("ERROR: Unable to read '{}': {}", cases_file.display(), e);
+      return false;
+    }
+  };
+
+  for error in &configuration.rule_set.errors {
+    println!("Ignored rule: {}", error.get_description());
+  }
+
+  for (index, test_case) in test_cases.iter().enumerate() {
+    let source_app:Option<SourceApp> = test_case.source_app.as_ref().map( |name| get_test_source_app(name) );
+    let actual:Option<BrowserSettings> = config::auto_launch_browser(&test_case.url, configuration, &source_app).map( |(_, browser)| browser );
+    let expected:Option<BrowserSettings>;
+    let expected_label:String;
+
+    match test_case.browser {
+      Some(ref reference) => {
+        expected = routing::get_browser(reference, &configuration.browsers_list);
+        expected_label = match expected {
+          Some(ref browser) => browser.title.to_string(),
+          None => format!("unknown browser '{}'", reference)
+        };
+      },
+      None => {
+        expected = None;
+        expected_label = String::from("main window");
+      }
+    };
+
+    let passed:bool = match (&test_case.browser, &expected, &actual) {
+      (Some(_), Some(expected), Some(actual)) => expected.title == actual.title,
+      (None, None, None) => true,
+      _ => false
+    };
+    if passed {
+      continue;
+    }
+
+    failed = failed + 1;
+    println!("@@ case {}: {}{} @@", index + 1, test_case.url, get_source_app_label(&test_case.source_app));
+    println!("-{}", expected_label);
+    println!("+{}", match actual {
+      Some(ref browser) => browser.title.to_string(),
+      None => String::from("main window")
+    });
+  }
+
+  println!("{} passed, {} failed", test_cases.len() - failed, failed);
+  if configuration.rule_set.errors.len() > 0 {
+    println!("{} rule(s) with invalid patterns", configuration.rule_set.errors.len());
+  }
+  return failed == 0 && configuration.rule_set.errors.len() == 0;
+}
+
+// Test cases only have the application name, use it for all the process details
+fn get_test_source_app(name:&str) -> SourceApp {
+  return SourceApp { pid: 0, name: name.to_string(), executable: name.to_string(), command_line: name.to_string() };
+}
+
+fn get_source_app_label(source_app:&Option<String>) -> String {
+  match source_app {
+    Some(name) => { return format!(" (from {})", name); },
+    None => { return String::new(); }
+  }
+}
@@ -126,7 +126,7 @@ pub fn check_application_files() {
     print_status(config::get_dotdesktop_path(false), config::BW_DOTDESKTOP, ".desktop\t");
     print_status(config::get_icon_path(false), config::BW_ICON_APPLICATION, "Icon\t\t");
 
-    print_status(config::get_configuration_path(), config::BW_CONFIG, "Configuration\t");
+    print_status(config::get_configuration_path(), config::get_config_file_name(&config::get_configuration_path()), "Configuration\t");
   }
 }
 
@@ -370,6 +370,8 @@ fn save_dotdesktop() {
   let mut dotdesktop_data:String;
   let system_path:String;
   let icon_file_path:String;
+  let supported_schemes:Vec<String>;
+  let mime_types:String;
 
   dotdesktop_raw = include_bytes!("../../resources/browsewith.desktop");
 
@@ -382,42 +384,63 @@ fn save_dotdesktop() {
   mimeapps_file.push("mimeapps.list");
   system_path = config::get_executable_path(is_admin).to_str().unwrap().to_string();
   icon_file_path = config::get_icon_file(is_admin).to_str().unwrap().to_string();
+  supported_schemes = config::get_supported_schemes();
+  mime_types = get_mime_types(&supported_schemes).iter().map( |m| format!("{};", m) ).collect::<String>();
+
+  // The .desktop file is always created, so that running '--install' again registers
+  // any URL scheme added to the configuration
+  dotdesktop_data = String::from_utf8_lossy(dotdesktop_raw).to_string();
+  dotdesktop_data = dotdesktop_data.replace("_SYSTEM_PATH_", &system_path);
+  dotdesktop_data = dotdesktop_data.replace("_ICON_FILE_", &icon_file_path);
+  dotdesktop_data = dotdesktop_data.replace("_MIME_TYPES_", &mime_types);
+
+  match write(&dotdesktop_file, dotdesktop_data) {
+    Ok(..) => {
+      if is_privileged_user() {
+        Command::new("update-desktop-database").output().expect("Failed to execute process");
+      } else {
+        modify_default_list(&mimeapps_file, true, &supported_schemes);
+      }
+    },
+    Err(..) => { println!("Failed to create '{:?}'", dotdesktop_file.to_str()); }
+  }
 
-  if !dotdesktop_file.is_file() {
-    dotdesktop_data = String::from_utf8_lossy(dotdesktop_raw).to_string();
-    dotdesktop_data = dotdesktop_data.replace("_SYSTEM_PATH_", &system_path);
-    dotdesktop_data = dotdesktop_data.replace("_ICON_FILE_", &icon_file_path);
-
-    match write(&dotdesktop_file, dotdesktop_data) {
-      Ok(..) => {
-        if is_privileged_user() {
-          Command::new("update-desktop-database").output().expect("Failed to execute process");
-        } else {
-          modify_default_list(&mimeapps_file, true);
-        }
-      },
-      Err(..) => { println!("Failed to create '{:?}'", dotdesktop_file.to_str()); }
+}
+
+// MIME types handled by BrowseWith, local files are handled through 'text/html'
+fn get_mime_types(supported_schemes:&Vec<String>) -> Vec<String> {
+  let mut mime_types:Vec<String> = vec!["text/html".to_string()];
+
+  for scheme in supported_schemes {
+    if scheme != "file" {
+      mime_types.push(format!("x-scheme-handler/{}", scheme));
     }
   }
 
+  return mime_types;
 }
 
-pub fn modify_default_list(file_path:&Path, install:bool) {
+pub fn modify_default_list(file_path:&Path, install:bool, supported_schemes:&Vec<String>) {
   let mut ini:Ini;
-  let mut iter:Iter<&str>;
+  let mut iter:Iter<String>;
   let mut new_value:String;
   let section:&mut Properties;
 
-  let mime_keys:Vec<&str> = [
-    "text/html",
-    "x-scheme-handler/http",
-    "x-scheme-handler/https"
-  ].to_vec();
+  let mut mime_keys:Vec<String> = get_mime_types(supported_schemes);
   let mut ini_changed:bool = false;
 
   ini = Ini::load_from_file(file_path).unwrap();
   section = ini.section_mut(Some("Added Associations")).unwrap();
 
+  // Schemes removed from the configuration after they were registered are still in the list
+  if !install {
+    for (key, _value) in section.iter() {
+      if key.starts_with("x-scheme-handler/") && !mime_keys.iter().any( |k| k == key ) {
+        mime_keys.push(key.to_string());
+      }
+    }
+  }
+
   iter = mime_keys.iter();
   loop {
     match iter.next() {
@@ -438,6 +461,9 @@ pub fn modify_default_list(file_path:&Path, install:bool) {
             },
             None => { }
           }
+        } else if install {
+          section.insert(key.to_string(), format!("{};", config::BW_DOTDESKTOP));
+          ini_changed = true;
         }
       },
       None => {
@@ -487,7 +513,7 @@ fn unregister_browsewith() {
   mimeapps_file = config::get_home_dir();
   mimeapps_file.push(".config");
   mimeapps_file.push("mimeapps.list");
-  if mimeapps_file.is_file() { modify_default_list(&mimeapps_file, false); }
+  if mimeapps_file.is_file() { modify_default_list(&mimeapps_file, false, &Vec::new()); }
 }
 
 fn remove_icon() {
@@ -175,7 +175,7 @@ pub fn check_application_files() {
   print_status(config::get_icon_path(false), config::BW_ICON_APPLICATION, "Icon\t\t");
   print_dll_status(config::get_executable_path(false), install_status.clone(), InstalledStatus::HAS_USER_DLLS);
 
-  print_status(config::get_configuration_path(), config::BW_CONFIG, "Configuration\t");
+  print_status(config::get_configuration_path(), config::get_config_file_name(&config::get_configuration_path()), "Configuration\t");
 }
 
 fn print_status(path:PathBuf, filename:&str, text:&str) {
@@ -297,8 +297,23 @@ fn set_registry_settings() -> std::io::Result<()> {
   registry_add_value(&reg_capabilities, "application/https", "BrowseWith.Assoc.1");
 
   (reg_capabilities, _disposition) = sub_key.create_subkey("Capabilities\\UrlAssociations")?;
-  registry_add_value(&reg_capabilities, "http", "BrowseWith.Assoc.1");
-  registry_add_value(&reg_capabilities, "https", "BrowseWith.Assoc.1");
+  for scheme in config::get_supported_schemes() {
+    if scheme != "file" {
+      registry_add_value(&reg_capabilities, &scheme, "BrowseWith.Assoc.1");
+    }
+  }
+
+  // Windows only knows http and https, other schemes need their own protocol class
+  for scheme in config::get_supported_schemes() {
+    if !is_builtin_scheme(&scheme) {
+      (sub_key, _disposition) = hkey_root.create_subkey(format!("SOFTWARE\\Classes\\{}", scheme))?;
+      registry_add_value(&sub_key, "", &format!("URL:{}", scheme));
+      registry_add_value(&sub_key, "URL Protocol", "");
+
+      (reg_capabilities, _disposition) = sub_key.create_subkey("shell\\open\\command")?;
+      registry_add_value(&reg_capabilities, "", &format!("{} \"%1\"", &executable_path.to_str().unwrap()));
+    }
+  }
 
   // ProgID associations
   (sub_key, _disposition) = hkey_root.create_subkey("SOFTWARE\\Classes\\BrowseWith.Assoc.1")?;
@@ -735,6 +750,7 @@ fn unregister_browsewith() {
     hkey_root = HKEY_CURRENT_USER;
   }
 
+  unregister_schemes(hkey_root, is_privileged_user());
   registry_remove_key(hkey_root, "Software\\BrowseWith.1");
   registry_remove_key(hkey_root, "SOFTWARE\\Classes\\BrowseWith.Assoc.1");
 
@@ -742,6 +758,43 @@ fn unregister_browsewith() {
 
 }
 
+fn is_builtin_scheme(scheme:&str) -> bool {
+  return ["http", "https", "file"].contains(&scheme);
+}
+
+// Remove the protocol classes of every scheme registered by BrowseWith, including schemes
+// that have since been removed from the configuration. Classes that now open another
+// program are left alone.
+fn unregister_schemes(hkey:HKEY, is_admin:bool) {
+  let hkey_root:RegKey;
+  let executable_path:PathBuf;
+  let url_associations:RegKey;
+  let mut command:String;
+
+  hkey_root = RegKey::predef(hkey);
+  executable_path = config::get_executable_file(is_admin);
+
+  match hkey_root.open_subkey("Software\\BrowseWith.1\\Capabilities\\UrlAssociations") {
+    Ok(key) => { url_associations = key; },
+    Err(..) => { return; }
+  }
+
+  for (scheme, _value) in url_associations.enum_values().filter_map( |v| v.ok() ) {
+    if is_builtin_scheme(&scheme) {
+      continue;
+    }
+    match hkey_root.open_subkey(format!("SOFTWARE\\Classes\\{}\\shell\\open\\command", scheme)) {
+      Ok(key) => {
+        command = registry_read_string(&key, "");
+        if command.starts_with(executable_path.to_str().unwrap()) {
+          registry_remove_key(hkey, &format!("SOFTWARE\\Classes\\{}", scheme));
+        }
+      },
+      Err(..) => { }
+    }
+  }
+}
+
 fn registry_add_value(path:&RegKey, key:&str, value:&str) {
   let v:Result<String, IoError>;
 
@@ -0,0 +1,124 @@
+use regex::Regex;
+
+#[cfg(any(target_os = "linux", target_os = "freebsd"))] use std::fs;
+#[cfg(any(target_os = "linux", target_os = "freebsd"))] use std::path::{ PathBuf };
+
+// Processes between the application and BrowseWith, e.g. 'xdg-open' runs a shell script
+// that starts BrowseWith. These are skipped when looking for the application.
+#[cfg(any(target_os = "linux", target_os = "freebsd"))]
+static LAUNCHER_PROCESSES:[&str; 14] = [
+  "sh", "bash", "dash", "zsh", "fish", "ksh", "csh", "tcsh",
+  "env", "xdg-open", "gio", "kde-open", "kde-open5", "exo-open"
+];
+#[cfg(any(target_os = "linux", target_os = "freebsd"))]
+static MAX_PARENT_DEPTH:usize = 10;
+
+#[derive(Clone, Debug)]
+pub struct SourceApp {
+  pub pid: u32,
+  pub name: String,
+  pub executable: String,
+  pub command_line: String
+}
+
+impl SourceApp {
+  // The pattern is matched against the process name, executable and command line
+  pub fn is_match(&self, re:&Regex) -> bool {
+    return re.is_match(&self.name) || re.is_match(&self.executable) || re.is_match(&self.command_line);
+  }
+
+  pub fn get_description(&self) -> String {
+    if self.executable == "" {
+      return format!("{} (pid {})", self.name, self.pid);
+    }
+    return format!("{} (pid {}, {})", self.name, self.pid, self.executable);
+  }
+}
+
+// Find the application that started BrowseWith by walking up the parent processes
+#[cfg(any(target_os = "linux", target_os = "freebsd"))]
+pub fn detect_source_app() -> Option<SourceApp> {
+  let mut pid:u32 = std::os::unix::process::parent_id();
+
+  for _ in 0..MAX_PARENT_DEPTH {
+    if pid <= 1 {
+      return None;
+    }
+    let (source_app, parent_pid) = read_process(pid)?;
+    if !LAUNCHER_PROCESSES.contains(&source_app.name.as_str()) {
+      return Some(source_app);
+    }
+    pid = parent_pid;
+  }
+
+  return None;
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "freebsd")))]
+pub fn detect_source_app() -> Option<SourceApp> {
+  return None;
+}
+
+// Returns the process information and its parent pid
+#[cfg(target_os = "linux")]
+fn read_process(pid:u32) -> Option<(SourceApp, u32)> {
+  let process_dir:PathBuf = PathBuf::from(format!("/proc/{}", pid));
+  let name:String;
+  let status:String;
+  let mut parent_pid:u32 = 0;
+
+  name = fs::read_to_string(process_dir.join("comm")).ok()?.trim().to_string();
+  status = fs::read_to_string(process_dir.join("status")).ok()?;
+  for line in status.lines() {
+    if line.starts_with("PPid:") {
+      parent_pid = line.trim_start_matches("PPid:").trim().parse().unwrap_or(0);
+    }
+  }
+
+  return Some((SourceApp {
+    pid: pid,
+    name: name,
+    executable: read_executable(&process_dir.join("exe")),
+    command_line: read_command_line(&process_dir.join("cmdline"))
+  }, parent_pid));
+}
+
+// FreeBSD procfs, '/proc/<pid>/status' starts with the command name, pid and parent pid
+#[cfg(target_os = "freebsd")]
+fn read_process(pid:u32) -> Option<(SourceApp, u32)> {
+  let process_dir:PathBuf = PathBuf::from(format!("/proc/{}", pid));
+  let status:String;
+  let fields:Vec<&str>;
+
+  status = fs::read_to_string(process_dir.join("status")).ok()?;
+  fields = status.split_whitespace().collect();
+  if fields.len() < 3 {
+    return None;
+  }
+
+  return Some((SourceApp {
+    pid: pid,
+    name: fields[0].to_string(),
+    executable: read_executable(&process_dir.join("file")),
+    command_line: read_command_line(&process_dir.join("cmdline"))
+  }, fields[2].parse().unwrap_or(0)));
+}
+
+#[cfg(any(target_os = "linux", target_os = "freebsd"))]
+fn read_executable(path:&PathBuf) -> String {
+  match fs::read_link(path) {
+    Ok(executable) => { return executable.to_string_lossy().to_string(); },
+    Err(..) => { return String::new(); }
+  }
+}
+
+// Arguments are separated by NUL characters
+#[cfg(any(target_os = "linux", target_os = "freebsd"))]
+fn read_command_line(path:&PathBuf) -> String {
+  match fs::read(path) {
+    Ok(bytes) => {
+      return String::from_utf8_lossy(&bytes).split('\0').filter( |a| *a != "" ).collect::<Vec<&str>>().join(" ");
+    },
+    Err(..) => { return String::new(); }
+  }
+}
@@ -0,0 +1,188 @@
+use url::{ Url, Host };
+use sha2::{ Sha256, Digest };
+use std::collections::{ HashSet };
+use std::path::{ Path, PathBuf };
+use std::fs;
+use std::fs::{ File };
+use std::io::{ Write };
+
+use crate::config;
+use crate::constants;
+
+// Hash prefixes are between 4 and 32 bytes, like the Safe Browsing update format
+static PREFIX_MIN_LENGTH:usize = 8;
+static PREFIX_MAX_LENGTH:usize = 64;
+
+pub struct ThreatList {
+  prefixes: HashSet<String>,
+  prefix_lengths: Vec<usize>
+}
+
+impl ThreatList {
+  // Returns 'None' if there isn't a threat list in the configuration directory
+  pub fn load() -> Option<ThreatList> {
+    let contents:String;
+    let prefixes:HashSet<String>;
+    let mut prefix_lengths:Vec<usize>;
+
+    contents = match fs::read_to_string(get_threat_list_file()) {
+      Ok(contents) => contents,
+      Err(..) => { return None; }
+    };
+
+    prefixes = match parse_threat_list(&contents) {
+      Ok(prefixes) => prefixes,
+      Err(e) => {
+        println!("Ignoring the threat list: {}", e);
+        return None;
+      }
+    };
+    prefix_lengths = prefixes.iter().map( |p| p.len() ).collect();
+    prefix_lengths.sort();
+    prefix_lengths.dedup();
+
+    return Some(ThreatList { prefixes: prefixes, prefix_lengths: prefix_lengths });
+  }
+
+  // Returns the URL expression matching the list, if any
+  pub fn lookup(&self, request_url:&str) -> Option<String> {
+    for expression in get_url_expressions(request_url) {
+      let hash:String = get_hash(&expression);
+      for length in &self.prefix_lengths {
+        if self.prefixes.contains(&hash[..*length]) {
+          return Some(expression);
+        }
+      }
+    }
+
+    return None;
+  }
+}
+
+pub fn get_threat_list_file() -> PathBuf {
+  let mut threat_list_file:PathBuf = config::get_config_dir();
+  threat_list_file.push(constants::THREATLIST_FILENAME);
+  return threat_list_file;
+}
+
+// Validate the new list and replace the current one. The list is written to a temporary
+// file first, so a failed import never leaves a partial list behind.
+pub fn import_threat_list(source:&Path) -> Result<usize, String> {
+  let contents:String;
+  let prefixes:HashSet<String>;
+  let threat_list_file:PathBuf = get_threat_list_file();
+  let mut temporary_file:PathBuf = threat_list_file.clone();
+  let mut file:File;
+
+  contents = fs::read_to_string(source).map_err( |e| format!("Unable to read '{}': {}", source.display(), e) )?;
+  prefixes = parse_threat_list(&contents)?;
+  // An empty or truncated download would turn off the protection, keep the current list instead
+  if prefixes.len() == 0 {
+    return Err(format!("'{}' doesn't have any hash prefixes, the current list is kept", source.display()));
+  }
+
+  if !config::get_config_dir().is_dir() {
+    fs::create_dir_all(config::get_config_dir()).map_err( |e| e.to_string() )?;
+  }
+
+  temporary_file.set_extension("tmp");
+  file = File::create(&temporary_file).map_err( |e| e.to_string() )?;
+  let mut sorted_prefixes:Vec<&String> = prefixes.iter().collect();
+  sorted_prefixes.sort();
+  for prefix in sorted_prefixes {
+    writeln!(file, "{}", prefix).map_err( |e| e.to_string() )?;
+  }
+  file.sync_all().map_err( |e| e.to_string() )?;
+
+  match fs::rename(&temporary_file, &threat_list_file) {
+    Ok(..) => { },
+    Err(e) => {
+      let _ = fs::remove_file(&temporary_file);
+      return Err(e.to_string());
+    }
+  }
+
+  return Ok(prefixes.len());
+}
+
+// One hexadecimal SHA-256 prefix per line, empty lines and lines starting with '#' are ignored
+fn parse_threat_list(contents:&str) -> Result<HashSet<String>, String> {
+  let mut prefixes:HashSet<String> = HashSet::new();
+
+  for (index, line) in contents.lines().enumerate() {
+    let line:&str = line.trim();
+    if line == "" || line.starts_with("#") {
+      continue;
+    }
+
+    let prefix:String = line.to_lowercase();
+    if prefix.len() < PREFIX_MIN_LENGTH || prefix.len() > PREFIX_MAX_LENGTH || prefix.len() % 2 != 0
+      || !prefix.chars().all( |c| c.is_ascii_hexdigit() ) {
+      return Err(format!("Invalid hash prefix on line {}: '{}'", index + 1, line));
+    }
+    prefixes.insert(prefix);
+  }
+
+  return Ok(prefixes);
+}
+
+fn get_hash(expression:&str) -> String {
+  return Sha256::digest(expression.as_bytes()).iter().map( |b| format!("{:02x}", b) ).collect();
+}
+
+// Host suffix and path prefix combinations checked against the list, as in the Safe
+// Browsing lookups. For 'a.b.c/1/2.html?p=1' these are 'a.b.c/1/2.html?p=1', 'a.b.c/1/2.html',
+// 'a.b.c/', 'a.b.c/1/', 'b.c/1/2.html?p=1', and so on.
+fn get_url_expressions(request_url:&str) -> Vec<String> {
+  let url:Url;
+  let mut hosts:Vec<String> = vec![];
+  let mut paths:Vec<String> = vec![];
+  let mut expressions:Vec<String> = vec![];
+
+  url = match Url::parse(request_url) {
+    Ok(url) => url,
+    Err(..) => { return expressions; }
+  };
+
+  match url.host() {
+    Some(Host::Domain(domain)) => {
+      let domain:String = domain.trim_end_matches(".").to_lowercase();
+      let components:Vec<&str> = domain.split(".").collect();
+      hosts.push(domain.clone());
+      // Up to four suffixes, made from the last five components
+      let first:usize = if components.len() > 5 { components.len() - 5 } else { 1 };
+      for i in first..components.len().saturating_sub(1) {
+        hosts.push(components[i..].join("."));
+      }
+    },
+    Some(host) => { hosts.push(host.to_string()); },
+    None => { return expressions; }
+  }
+
+  match url.query() {
+    Some(query) => { paths.push(format!("{}?{}", url.path(), query)); },
+    None => { }
+  }
+  paths.push(url.path().to_string());
+
+  // Up to four path prefixes, starting with '/' and adding one directory at a time
+  let segments:Vec<&str> = url.path().split("/").collect();
+  let mut prefix:String = String::from("/");
+  for i in 0..4 {
+    if !paths.contains(&prefix) {
+      paths.push(prefix.clone());
+    }
+    if i + 2 >= segments.len() {
+      break;
+    }
+    prefix = format!("{}{}/", prefix, segments[i + 1]);
+  }
+
+  for host in &hosts {
+    for path in &paths {
+      expressions.push(format!("{}{}", host, path));
+    }
+  }
+
+  return expressions;
+}
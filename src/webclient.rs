@@ -1,8 +1,18 @@
 use url::{ Url };
+use regex::Regex;
+use std::time::{ Duration };
 
-pub fn validate_url(request_url:&str) -> bool {
-  let supported_schemes:Vec<String> = vec!["http".to_string(), "https".to_string(), "ftp".to_string(), "file".to_string()];
+use crate::config::{ Redirector, RedirectPolicy, TrackingPolicy, RewriteRule, ShortLinkPolicy };
+use crate::constants;
 
+#[derive(Clone)]
+pub struct StrippedUrl {
+  pub url: String,
+  pub original: String,
+  pub parameters: Vec<String>
+}
+
+pub fn validate_url(request_url:&str, supported_schemes:&Vec<String>) -> bool {
   match Url::parse(request_url) {
     Ok(url) => {
       if supported_schemes.contains(&url.scheme().to_lowercase()) {
@@ -13,3 +23,353 @@ pub fn validate_url(request_url:&str) -> bool {
     Err(..) => { return false }
   };
 }
+
+// Redirectors known to wrap links in emails and chat messages, user defined
+// redirectors from 'config.json' are checked after these
+pub fn get_known_redirectors() -> Vec<Redirector> {
+  let known_redirectors:Vec<(&str, &str, &str, &str)> = vec![
+    ("Outlook SafeLinks", r"^([a-z0-9-]+\.)*safelinks\.protection\.outlook\.com$", r"^/", "url"),
+    ("Google", r"^(www\.)?google\.[a-z]+(\.[a-z]+)?$", r"^/url$", "q"),
+    ("Google", r"^(www\.)?google\.[a-z]+(\.[a-z]+)?$", r"^/url$", "url"),
+    ("Facebook", r"^lm?\.facebook\.com$", r"^/l\.php$", "u"),
+    ("Slack", r"^slack-redir\.net$", r"^/link$", "url")
+  ];
+
+  return known_redirectors.iter().map( |(name, host, path, parameter)| {
+    Redirector { name: name.to_string(), host: host.to_string(), path: path.to_string(), parameter: parameter.to_string() }
+  }).collect();
+}
+
+pub fn unwrap_url(request_url:&str, redirect_policy:&Option<RedirectPolicy>, supported_schemes:&Vec<String>) -> String {
+  let mut redirectors:Vec<Redirector> = get_known_redirectors();
+  let mut unwrapped_url:String = request_url.to_string();
+
+  match redirect_policy {
+    Some(policy) => {
+      // Keep the original URL
+      if !policy.unwrap {
+        return unwrapped_url;
+      }
+      redirectors.extend(policy.redirectors.iter().cloned());
+    },
+    None => { }
+  }
+
+  // Links can be wrapped more than once, e.g. a Google redirect inside SafeLinks
+  for _ in 0..constants::REDIRECTS_MAX_DEPTH {
+    match get_redirect_target(&unwrapped_url, &redirectors, supported_schemes) {
+      Some(target) => { unwrapped_url = target; },
+      None => { break; }
+    }
+  }
+
+  return unwrapped_url;
+}
+
+fn get_redirect_target(request_url:&str, redirectors:&Vec<Redirector>, supported_schemes:&Vec<String>) -> Option<String> {
+  let url:Url;
+  let host:String;
+
+  url = match Url::parse(request_url) {
+    Ok(url) => url,
+    Err(..) => { return None; }
+  };
+  host = match url.host_str() {
+    Some(host) => host.to_lowercase(),
+    None => { return None; }
+  };
+
+  for redirector in redirectors {
+    if !is_match(&redirector.host, &host) || !is_match(&redirector.path, url.path()) {
+      continue;
+    }
+    // Query values are already percent decoded by 'query_pairs'
+    for (key, value) in url.query_pairs() {
+      if key == redirector.parameter && validate_url(&value, supported_schemes) {
+        return Some(value.to_string());
+      }
+    }
+  }
+
+  return None;
+}
+
+fn is_match(pattern:&str, text:&str) -> bool {
+  match Regex::new(pattern) {
+    Ok(re) => { return re.is_match(text); },
+    Err(..) => {
+      println!("Invalid redirector pattern: '{}'", pattern);
+      return false;
+    }
+  }
+}
+
+// Remove tracking parameters from the query string, returns 'None' if the URL wasn't changed
+pub fn strip_tracking_parameters(request_url:&str, tracking_policy:&Option<TrackingPolicy>) -> Option<StrippedUrl> {
+  let policy:&TrackingPolicy;
+  let mut url:Url;
+  let host:String;
+  let query:String;
+  let mut kept_pairs:Vec<&str> = vec![];
+  let mut removed_parameters:Vec<String> = vec![];
+
+  policy = match tracking_policy {
+    Some(policy) if policy.strip => policy,
+    _ => { return None; }
+  };
+  url = match Url::parse(request_url) {
+    Ok(url) => url,
+    Err(..) => { return None; }
+  };
+  host = url.host_str().unwrap_or("").to_lowercase();
+  query = match url.query() {
+    Some(query) => query.to_string(),
+    None => { return None; }
+  };
+
+  // Domains in the exceptions list also match their subdomains
+  for exception in &policy.exceptions {
+    let domain:String = exception.trim_start_matches(".").to_lowercase();
+    if host == domain || host.ends_with(&format!(".{}", domain)) {
+      return None;
+    }
+  }
+
+  // Work with the raw query so that the parameters we keep aren't re-encoded
+  for pair in query.split("&") {
+    let key:String = match pair.split("=").next() {
+      Some(key) => key.to_string(),
+      None => String::new()
+    };
+    if is_tracking_parameter(&key, &policy.parameters) {
+      removed_parameters.push(key);
+    } else {
+      kept_pairs.push(pair);
+    }
+  }
+
+  if removed_parameters.len() == 0 {
+    return None;
+  }
+
+  if kept_pairs.len() == 0 {
+    url.set_query(None);
+  } else {
+    url.set_query(Some(&kept_pairs.join("&")));
+  }
+
+  return Some(StrippedUrl {
+    url: url.to_string(),
+    original: request_url.to_string(),
+    parameters: removed_parameters
+  });
+}
+
+// Parameters ending with '*' match any parameter starting with the same prefix
+fn is_tracking_parameter(key:&str, parameters:&Vec<String>) -> bool {
+  let key:String = key.to_lowercase();
+
+  for parameter in parameters {
+    let parameter:String = parameter.to_lowercase();
+    if parameter.ends_with("*") {
+      if key.starts_with(parameter.trim_end_matches("*")) {
+        return true;
+      }
+    } else if key == parameter {
+      return true;
+    }
+  }
+
+  return false;
+}
+
+// Apply the rewrite rules in order, each rule receives the URL returned by the previous one
+pub fn rewrite_url(request_url:&str, rewrite_rules:&Option<Vec<RewriteRule>>, supported_schemes:&Vec<String>) -> String {
+  let mut rewritten_url:String = request_url.to_string();
+  let rules:&Vec<RewriteRule>;
+
+  rules = match rewrite_rules {
+    Some(rules) => rules,
+    None => { return rewritten_url; }
+  };
+
+  for rule in rules {
+    let re:Regex = match Regex::new(&rule.pattern) {
+      Ok(re) => re,
+      Err(..) => {
+        println!("Invalid rewrite rule pattern: '{}'", rule.pattern);
+        continue;
+      }
+    };
+    if !re.is_match(&rewritten_url) {
+      continue;
+    }
+
+    let new_url:String = re.replace(&rewritten_url, rule.replacement.as_str()).to_string();
+    if validate_url(&new_url, supported_schemes) {
+      rewritten_url = new_url;
+    } else {
+      println!("Ignoring rewrite rule '{}', '{}' isn't a valid URL", rule.pattern, new_url);
+    }
+  }
+
+  return rewritten_url;
+}
+
+// Follow the redirects of link shorteners to find the destination URL. Only the hosts in the
+// shortener list are contacted, the destination website never receives a request.
+pub fn expand_url(request_url:&str, shortlink_policy:&Option<ShortLinkPolicy>, supported_schemes:&Vec<String>) -> String {
+  let policy:&ShortLinkPolicy;
+  let agent:ureq::Agent;
+  let mut expanded_url:String = request_url.to_string();
+
+  policy = match shortlink_policy {
+    Some(policy) if policy.expand => policy,
+    _ => { return expanded_url; }
+  };
+
+  // Handle the redirects here, so that we can stop once we leave the shortener domains
+  agent = ureq::AgentBuilder::new()
+    .redirects(0)
+    .timeout(Duration::from_millis(policy.timeout))
+    .build();
+
+  for _ in 0..policy.max_redirects {
+    let url:Url = match Url::parse(&expanded_url) {
+      Ok(url) => url,
+      Err(..) => { break; }
+    };
+    if !is_shortener(url.host_str().unwrap_or(""), &policy.domains) {
+      break;
+    }
+
+    match agent.head(url.as_str()).call() {
+      Ok(response) if response.status() >= 300 && response.status() < 400 => {
+        let location:Option<Url> = match response.header("location") {
+          Some(location) => url.join(location).ok(),
+          None => None
+        };
+        match location {
+          Some(location) if validate_url(location.as_str(), supported_schemes) => {
+            expanded_url = location.to_string();
+          },
+          _ => { break; }
+        }
+      },
+      Ok(..) => { break; },
+      Err(error) => {
+        println!("Failed to expand '{}': {}", expanded_url, error);
+        break;
+      }
+    }
+  }
+
+  return expanded_url;
+}
+
+fn is_shortener(host:&str, domains:&Vec<String>) -> bool {
+  let host:String = host.to_lowercase();
+
+  return domains.iter().any( |domain| {
+    let domain:String = domain.to_lowercase();
+    host == domain || host.ends_with(&format!(".{}", domain))
+  });
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use std::io::{ BufRead, BufReader, Write };
+  use std::net::{ TcpListener, TcpStream };
+  use std::sync::{ Arc };
+  use std::sync::atomic::{ AtomicUsize, Ordering };
+  use std::time::{ Instant };
+
+  // Stub link shortener on a local port, every request is answered with a redirect to the location
+  // returned by 'get_location' for the server address and the request path. '/slow' answers late.
+  fn start_server(get_location:fn(&str, &str) -> String) -> (String, Arc<AtomicUsize>) {
+    let listener:TcpListener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let address:String = format!("http://{}", listener.local_addr().unwrap());
+    let requests:Arc<AtomicUsize> = Arc::new(AtomicUsize::new(0));
+    let server_address:String = address.clone();
+    let server_requests:Arc<AtomicUsize> = requests.clone();
+
+    std::thread::spawn( move || {
+      for stream in listener.incoming() {
+        let mut stream:TcpStream = match stream {
+          Ok(stream) => stream,
+          Err(..) => { break; }
+        };
+        let mut reader:BufReader<TcpStream> = BufReader::new(stream.try_clone().unwrap());
+        let mut request_line:String = String::new();
+        let path:String;
+
+        let _ = reader.read_line(&mut request_line);
+        loop {
+          let mut header:String = String::new();
+          if reader.read_line(&mut header).unwrap_or(0) <= 2 {
+            break;
+          }
+        }
+        server_requests.fetch_add(1, Ordering::SeqCst);
+
+        path = request_line.split_whitespace().nth(1).unwrap_or("/").to_string();
+        if path == "/slow" {
+          std::thread::sleep(Duration::from_millis(2000));
+        }
+        let _ = write!(stream, "HTTP/1.1 301 Moved Permanently\r\nLocation: {}\r\nContent-Length: 0\r\nConnection: close\r\n\r\n", get_location(&server_address, &path));
+      }
+    });
+
+    return (address, requests);
+  }
+
+  fn get_policy(max_redirects:u32, timeout:u64) -> Option<ShortLinkPolicy> {
+    return Some(ShortLinkPolicy {
+      expand: true,
+      domains: vec![String::from("127.0.0.1")],
+      max_redirects: max_redirects,
+      timeout: timeout
+    });
+  }
+
+  fn get_schemes() -> Vec<String> {
+    return vec![String::from("http"), String::from("https")];
+  }
+
+  #[test]
+  fn stops_at_redirect_limit() {
+    // '/1' redirects to '/2', '/2' to '/3' and so on
+    let (address, requests) = start_server( |address, path| {
+      format!("{}/{}", address, path.trim_start_matches("/").parse::<u32>().unwrap_or(0) + 1)
+    });
+
+    assert_eq!(expand_url(&format!("{}/1", address), &get_policy(3, 1000), &get_schemes()), format!("{}/4", address));
+    assert_eq!(requests.load(Ordering::SeqCst), 3);
+  }
+
+  #[test]
+  fn stops_at_destination_host() {
+    let (address, requests) = start_server( |_, _| String::from("https://www.example.com/article?id=1") );
+
+    assert_eq!(expand_url(&format!("{}/abc", address), &get_policy(5, 1000), &get_schemes()), "https://www.example.com/article?id=1");
+    assert_eq!(requests.load(Ordering::SeqCst), 1);
+  }
+
+  #[test]
+  fn keeps_url_after_timeout() {
+    let (address, _) = start_server( |_, _| String::from("https://www.example.com/") );
+    let start:Instant = Instant::now();
+
+    assert_eq!(expand_url(&format!("{}/slow", address), &get_policy(5, 200), &get_schemes()), format!("{}/slow", address));
+    assert!(start.elapsed() < Duration::from_millis(1500));
+  }
+
+  #[test]
+  fn ignores_unsupported_scheme() {
+    let (address, requests) = start_server( |_, _| String::from("ftp://files.example.com/setup.exe") );
+
+    assert_eq!(expand_url(&format!("{}/abc", address), &get_policy(5, 1000), &get_schemes()), format!("{}/abc", address));
+    assert_eq!(requests.load(Ordering::SeqCst), 1);
+  }
+}